@@ -0,0 +1,526 @@
+// The scanner as it was before switching to byte offsets and interned
+// lexemes, kept verbatim apart from its token type as a benchmark baseline.
+
+use itertools::Itertools;
+use itertools::MultiPeek;
+use std::collections::HashMap;
+use std::str::{Chars, FromStr};
+use unicode_normalization::UnicodeNormalization;
+use unicode_xid::UnicodeXID;
+
+use lox_rs::error::LoxError;
+use lox_rs::token::{Literal, Span, TokenType};
+
+// the token type from before lexemes were interned, each one owning its text
+#[allow(dead_code)]
+pub struct Token {
+    pub token_type: TokenType,
+    pub lexeme: String,
+    pub literal: Option<Literal>,
+    pub span: Span,
+}
+
+impl Token {
+    fn new(token_type: TokenType, lexeme: String, literal: Option<Literal>, span: Span) -> Self {
+        Self {
+            token_type,
+            lexeme,
+            literal,
+            span,
+        }
+    }
+}
+
+pub struct Scanner<'a> {
+    source: MultiPeek<Chars<'a>>,
+    curr_buf: Vec<char>,
+    line: u32,
+    // characters consumed so far on the current line
+    column: u32,
+    // bytes consumed so far
+    offset: usize,
+    // position of the first character of the token being scanned
+    start: usize,
+    start_line: u32,
+    start_column: u32,
+    keywords: HashMap<String, TokenType>,
+    // one entry per `${` still open, holding the number of unclosed braces
+    // inside it and the span of the `${` for error reporting
+    interpolations: Vec<(usize, Span)>,
+    errors: Vec<LoxError>,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self {
+            source: source.chars().multipeek(),
+            curr_buf: Vec::new(),
+            line: 1,
+            column: 0,
+            offset: 0,
+            start: 0,
+            start_line: 1,
+            start_column: 1,
+            keywords: HashMap::from_iter([
+                ("and".to_string(), TokenType::And),
+                ("class".to_string(), TokenType::Class),
+                ("else".to_string(), TokenType::Else),
+                ("false".to_string(), TokenType::False),
+                ("for".to_string(), TokenType::For),
+                ("fun".to_string(), TokenType::Fun),
+                ("if".to_string(), TokenType::If),
+                ("nil".to_string(), TokenType::Nil),
+                ("or".to_string(), TokenType::Or),
+                ("print".to_string(), TokenType::Print),
+                ("return".to_string(), TokenType::Return),
+                ("super".to_string(), TokenType::Super),
+                ("this".to_string(), TokenType::This),
+                ("true".to_string(), TokenType::True),
+                ("var".to_string(), TokenType::Var),
+                ("while".to_string(), TokenType::While),
+            ]),
+            interpolations: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Scans the whole source, returning the tokens alongside any lexical
+    /// errors. Invalid input is reported as an error and left out of the
+    /// token stream.
+    pub fn scan_tokens(&mut self) -> (Vec<Token>, Vec<LoxError>) {
+        let mut tokens = Vec::new();
+        while let Some(token) = self.scan_token() {
+            if token.token_type != TokenType::Ignore {
+                tokens.push(token);
+            }
+        }
+
+        for (_, span) in std::mem::take(&mut self.interpolations) {
+            self.error_at(span, "Unterminated string interpolation.");
+        }
+
+        let eof = Span::new(self.offset, self.offset, self.line, self.column + 1);
+        tokens.push(Token::new(TokenType::Eof, "".to_string(), None, eof));
+        (tokens, std::mem::take(&mut self.errors))
+    }
+
+    fn scan_token(&mut self) -> Option<Token> {
+        self.start = self.offset;
+        self.start_line = self.line;
+        self.start_column = self.column + 1;
+        let c = self.advance()?;
+        let token = match c {
+            '(' => self.finalize_token(TokenType::LeftParens),
+            ')' => self.finalize_token(TokenType::RightParens),
+            '{' => {
+                if let Some((depth, _)) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.finalize_token(TokenType::LeftBrace)
+            }
+            '}' => match self.interpolations.last_mut() {
+                // closes the innermost `${`, the string carries on from here
+                Some((0, _)) => {
+                    self.interpolations.pop();
+                    self.string()
+                }
+                Some((depth, _)) => {
+                    *depth -= 1;
+                    self.finalize_token(TokenType::RightBrace)
+                }
+                None => self.finalize_token(TokenType::RightBrace),
+            },
+            ',' => self.finalize_token(TokenType::Comma),
+            '.' => self.finalize_token(TokenType::Dot),
+            ';' => self.finalize_token(TokenType::SemiColon),
+            '%' => self.finalize_token(TokenType::Percent),
+            '+' => {
+                if self.advance_on_match('+') {
+                    self.finalize_token(TokenType::PlusPlus)
+                } else if self.advance_on_match('=') {
+                    self.finalize_token(TokenType::PlusEqual)
+                } else {
+                    self.finalize_token(TokenType::Plus)
+                }
+            }
+            '-' => {
+                if self.advance_on_match('-') {
+                    self.finalize_token(TokenType::MinusMinus)
+                } else if self.advance_on_match('=') {
+                    self.finalize_token(TokenType::MinusEqual)
+                } else {
+                    self.finalize_token(TokenType::Minus)
+                }
+            }
+            '*' => {
+                if self.advance_on_match('=') {
+                    self.finalize_token(TokenType::StarEqual)
+                } else {
+                    self.finalize_token(TokenType::Star)
+                }
+            }
+            '!' => {
+                if self.advance_on_match('=') {
+                    self.finalize_token(TokenType::BangEqual)
+                } else {
+                    self.finalize_token(TokenType::Bang)
+                }
+            }
+            '=' => {
+                if self.advance_on_match('=') {
+                    self.finalize_token(TokenType::EqualEqual)
+                } else {
+                    self.finalize_token(TokenType::Equal)
+                }
+            }
+            '<' => {
+                if self.advance_on_match('=') {
+                    self.finalize_token(TokenType::LessEqual)
+                } else {
+                    self.finalize_token(TokenType::Less)
+                }
+            }
+            '>' => {
+                if self.advance_on_match('=') {
+                    self.finalize_token(TokenType::GreaterEqual)
+                } else {
+                    self.finalize_token(TokenType::Greater)
+                }
+            }
+            '/' => {
+                if self.advance_on_match('/') {
+                    self.advance_until(|c| c != &'\n');
+                    self.curr_buf.clear();
+                    self.scan_token()?
+                } else if self.advance_on_match('*') {
+                    self.block_comment()
+                } else if self.advance_on_match('=') {
+                    self.finalize_token(TokenType::SlashEqual)
+                } else {
+                    self.finalize_token(TokenType::Slash)
+                }
+            }
+            ' ' | '\r' | '\t' | '\n' => self.finalize_token(TokenType::Ignore),
+            '"' => self.string(),
+            d if d.is_ascii_digit() => self.number(d),
+            c if c.is_xid_start() || c == '_' => {
+                self.advance_until(|c| c.is_xid_continue());
+
+                // identifiers spelled with different but canonically equivalent
+                // code points refer to the same variable
+                let lexeme = self.curr_buf.drain(..).nfc().collect::<String>();
+                let token_type = match self.keywords.get(&lexeme) {
+                    Some(t) => *t,
+                    None => TokenType::Identifer,
+                };
+
+                Token {
+                    token_type,
+                    lexeme,
+                    literal: None,
+                    span: self.span(),
+                }
+            }
+            _ => self.error("Unexpected character."),
+        };
+
+        Some(token)
+    }
+
+    // decimal literals may have a fraction and an exponent, `0x` and `0b`
+    // prefixes introduce hexadecimal and binary integers. Any of them may use
+    // `_` between digits as a separator
+    fn number(&mut self, first: char) -> Token {
+        if first == '0' {
+            match self.peek() {
+                Some('x') | Some('X') => return self.radix_number(16, "hexadecimal"),
+                Some('b') | Some('B') => return self.radix_number(2, "binary"),
+                _ => {}
+            }
+        }
+
+        self.advance_until(|c| c.is_ascii_digit() || c == &'_');
+        if self.peek() == Some('.') && self.peek_next().is_some_and(|c| c.is_ascii_digit()) {
+            self.advance();
+            self.advance_until(|c| c.is_ascii_digit() || c == &'_');
+        }
+        if let Some('e') | Some('E') = self.peek() {
+            self.advance();
+            if let Some('+') | Some('-') = self.peek() {
+                self.advance();
+            }
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return self.error("Expect digits in number exponent.");
+            }
+            self.advance_until(|c| c.is_ascii_digit() || c == &'_');
+        }
+
+        let lexeme = String::from_iter(&self.curr_buf);
+        if !separators_between(&lexeme, |c| c.is_ascii_digit()) {
+            return self.error("Digit separators must be placed between digits.");
+        }
+        match f64::from_str(&lexeme.replace('_', "")) {
+            Ok(n) => self.number_token(n),
+            Err(_) => self.error("Failed to parse number."),
+        }
+    }
+
+    fn radix_number(&mut self, radix: u32, name: &str) -> Token {
+        self.advance();
+        self.advance_until(|c| c.is_ascii_alphanumeric() || c == &'_');
+
+        let lexeme = String::from_iter(&self.curr_buf);
+        let (prefix, digits) = lexeme.split_at(2);
+        if digits.is_empty() {
+            return self.error(&format!("Expect {} digits after '{}'.", name, prefix));
+        }
+        if let Some(c) = digits.chars().find(|c| c != &'_' && !c.is_digit(radix)) {
+            return self.error(&format!("Invalid digit '{}' in {} literal.", c, name));
+        }
+        if !separators_between(digits, |c| c.is_digit(radix)) {
+            return self.error("Digit separators must be placed between digits.");
+        }
+        match u64::from_str_radix(&digits.replace('_', ""), radix) {
+            Ok(n) => self.number_token(n as f64),
+            Err(_) => self.error("Number literal is too large."),
+        }
+    }
+
+    fn number_token(&mut self, value: f64) -> Token {
+        Token {
+            literal: Some(Literal::Number(value)),
+            ..self.finalize_token(TokenType::Number)
+        }
+    }
+
+    // skips a `/* ... */` comment, which may contain nested block comments
+    fn block_comment(&mut self) -> Token {
+        let mut depth = 1;
+        while depth > 0 {
+            match (self.peek(), self.peek_next()) {
+                (None, _) => {
+                    // point at the opening delimiter rather than the whole file
+                    let span = Span::new(
+                        self.start,
+                        self.start + 2,
+                        self.start_line,
+                        self.start_column,
+                    );
+                    self.error_at(span, "Unterminated block comment.");
+                    break;
+                }
+                (Some('*'), Some('/')) => {
+                    self.advance();
+                    self.advance();
+                    depth -= 1;
+                }
+                (Some('/'), Some('*')) => {
+                    self.advance();
+                    self.advance();
+                    depth += 1;
+                }
+                _ => {
+                    self.advance();
+                }
+            }
+        }
+        self.finalize_token(TokenType::Ignore)
+    }
+
+    // the token's lexeme is the raw text between the quotes while its literal
+    // holds the value with escape sequences processed. A string containing
+    // `${` is split into an `Interpolation` token for each segment ending in
+    // `${`, followed by the tokens of the embedded expression, and finally a
+    // `String` token for the rest once the matching `}` is reached
+    fn string(&mut self) -> Token {
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None => return self.error("Unterminated string."),
+                Some('"') => break,
+                Some('$') if self.peek_next() == Some('{') => {
+                    let start = Span::new(self.offset, self.offset + 2, self.line, self.column + 1);
+                    self.advance();
+                    self.advance();
+                    self.interpolations.push((0, start));
+
+                    let raw = String::from_iter(self.curr_buf.drain(..));
+                    return Token {
+                        token_type: TokenType::Interpolation,
+                        lexeme: raw[1..raw.len() - 2].to_string(),
+                        literal: Some(Literal::Str(value)),
+                        span: self.span(),
+                    };
+                }
+                Some('\\') => {
+                    let start = (self.offset, self.line, self.column + 1);
+                    self.advance();
+                    match self.escape() {
+                        Ok(c) => value.push(c),
+                        Err(msg) => {
+                            let (offset, line, column) = start;
+                            let span = Span::new(offset, self.offset, line, column);
+                            self.error_at(span, msg);
+                        }
+                    }
+                }
+                Some(_) => {
+                    if let Some(c) = self.advance() {
+                        value.push(c);
+                    }
+                }
+            }
+        }
+
+        // Consume last '"'
+        self.advance();
+        let raw = String::from_iter(self.curr_buf.drain(..));
+        let lexeme = raw[1..raw.len() - 1].to_string();
+        Token {
+            token_type: TokenType::String,
+            lexeme,
+            literal: Some(Literal::Str(value)),
+            span: self.span(),
+        }
+    }
+
+    // decodes the escape sequence following a backslash
+    fn escape(&mut self) -> Result<char, &'static str> {
+        match self.peek() {
+            Some('n') | Some('t') | Some('r') | Some('\\') | Some('"') | Some('$') => {
+                let c = self.advance().unwrap_or_default();
+                Ok(match c {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    c => c,
+                })
+            }
+            Some('u') => {
+                self.advance();
+                if self.peek() != Some('{') {
+                    return Err("Expect '{' after '\\u' in unicode escape.");
+                }
+                self.advance();
+
+                let mut digits = String::new();
+                while let Some(c) = self.peek().filter(|c| c.is_ascii_hexdigit()) {
+                    self.advance();
+                    digits.push(c);
+                }
+
+                if self.peek() != Some('}') {
+                    return Err("Expect '}' after unicode escape digits.");
+                }
+                self.advance();
+
+                if digits.is_empty() || digits.len() > 6 {
+                    return Err("Unicode escape must have between 1 and 6 hex digits.");
+                }
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or("Invalid unicode code point in escape.")
+            }
+            // leave the end of input to be reported as an unterminated string
+            None => Err("Unterminated escape sequence."),
+            Some(_) => {
+                self.advance();
+                Err("Invalid escape sequence.")
+            }
+        }
+    }
+
+    // next character without consuming it, unlike `source.peek` this always
+    // looks at the character immediately after the last one consumed
+    fn peek(&mut self) -> Option<char> {
+        self.source.reset_peek();
+        self.source.peek().copied()
+    }
+
+    fn peek_next(&mut self) -> Option<char> {
+        self.source.reset_peek();
+        self.source.peek();
+        let c = self.source.peek().copied();
+        self.source.reset_peek();
+        c
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.source.next()?;
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
+        self.curr_buf.push(c);
+        Some(c)
+    }
+
+    fn advance_until<F>(&mut self, f: F) -> Option<char>
+    where
+        F: Fn(&char) -> bool,
+    {
+        self.source.reset_peek();
+        loop {
+            let next = self.source.peek()?;
+            if f(next) {
+                self.advance();
+            } else {
+                return Some(*next);
+            }
+        }
+    }
+
+    fn advance_on_match(&mut self, c: char) -> bool {
+        if Some(&c) == self.source.peek() {
+            self.advance();
+            true
+        } else {
+            // rewind the peek cursor so matches can be chained
+            self.source.reset_peek();
+            false
+        }
+    }
+
+    fn finalize_token(&mut self, token_type: TokenType) -> Token {
+        let lexeme = String::from_iter(self.curr_buf.drain(..));
+        Token {
+            token_type,
+            lexeme,
+            literal: None,
+            span: self.span(),
+        }
+    }
+
+    // span of the token being scanned, from its first character up to the
+    // last character consumed
+    fn span(&self) -> Span {
+        Span::new(self.start, self.offset, self.start_line, self.start_column)
+    }
+
+    // records a lexical error covering the current token, which is then
+    // dropped from the token stream
+    fn error(&mut self, msg: &str) -> Token {
+        self.error_at(self.span(), msg);
+        self.finalize_token(TokenType::Ignore)
+    }
+
+    fn error_at(&mut self, span: Span, msg: &str) {
+        self.errors.push(LoxError::ScannerError(msg.into(), span));
+    }
+}
+
+// whether every `_` in a numeric literal sits between two digits
+fn separators_between<F>(literal: &str, is_digit: F) -> bool
+where
+    F: Fn(char) -> bool,
+{
+    let chars: Vec<char> = literal.chars().collect();
+    chars.iter().enumerate().all(|(i, c)| {
+        c != &'_'
+            || (i > 0 && is_digit(chars[i - 1]) && chars.get(i + 1).is_some_and(|&c| is_digit(c)))
+    })
+}
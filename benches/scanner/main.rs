@@ -0,0 +1,85 @@
+//! Compares the throughput of `Scanner` against the char-buffer scanner it
+//! replaced, on a generated multi-megabyte script.
+//!
+//! Run with `cargo bench --bench scanner`.
+
+mod legacy;
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use lox_rs::scanner::Scanner;
+
+const SOURCE_SIZE: usize = 8 * 1024 * 1024;
+const ITERATIONS: usize = 10;
+
+// exercises every kind of token the scanner produces
+const SNIPPET: &str = r#"
+// configuration generated for node ${id}
+class Node < Base {
+  init(name, weight) {
+    this.name = name;
+    this.weight = weight * 1.5e-3 + 0xFF_FF;
+  }
+
+  /* describe the node,
+     /* nested */ across lines */
+  describe() {
+    return "node ${this.name} weighs ${this.weight}\n";
+  }
+}
+
+var mask = 0b1010_1010;
+var total = 0;
+for (var i = 0; i < 1_000; i += 1) {
+  if (i % 3 == 0 and i != 42 or !(total >= 100)) total++;
+  else total -= i / 2;
+}
+print Node("café", total).describe();
+"#;
+
+fn main() {
+    let source = SNIPPET.repeat(SOURCE_SIZE / SNIPPET.len() + 1);
+
+    let (legacy_tokens, _) = legacy::Scanner::new(&source).scan_tokens();
+    let (tokens, _) = Scanner::new(&source).scan_tokens();
+    assert_eq!(legacy_tokens.len(), tokens.len());
+
+    println!(
+        "scanning {:.1} MiB, {} tokens, best of {} runs",
+        source.len() as f64 / (1024.0 * 1024.0),
+        tokens.len(),
+        ITERATIONS
+    );
+    let legacy = measure(|| legacy::Scanner::new(&source).scan_tokens());
+    report("legacy", source.len(), legacy);
+    let current = measure(|| Scanner::new(&source).scan_tokens());
+    report("current", source.len(), current);
+    println!(
+        "speedup: {:.2}x",
+        legacy.as_secs_f64() / current.as_secs_f64()
+    );
+}
+
+fn measure<T, F>(mut scan: F) -> Duration
+where
+    F: FnMut() -> T,
+{
+    (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(scan());
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn report(name: &str, bytes: usize, elapsed: Duration) {
+    println!(
+        "{:>8}: {:>8.2?} ({:.1} MiB/s)",
+        name,
+        elapsed,
+        bytes as f64 / (1024.0 * 1024.0) / elapsed.as_secs_f64()
+    );
+}
@@ -0,0 +1,190 @@
+use crate::{
+    parser::{Expr, FunctionStmt, Literal, Stmt},
+    token::Token,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AstFormat {
+    // `(* (- 123) (group 45.67))`
+    SExpr,
+    // one node per line, children indented below their parent
+    Tree,
+}
+
+/// Renders the syntax tree as the interpreter sees it, after desugaring, for
+/// debugging the parser.
+///
+/// ```text
+/// (print (* (- 123) (group 45.67)))
+/// ```
+pub struct AstPrinter {
+    format: AstFormat,
+}
+
+// an operator or keyword applied to its operands, or a name or literal
+enum Node {
+    Atom(String),
+    List(String, Vec<Node>),
+}
+
+impl AstPrinter {
+    pub fn new(format: AstFormat) -> Self {
+        Self { format }
+    }
+
+    /// One statement after another, each ending with a newline.
+    pub fn print(&self, statements: &[Stmt]) -> String {
+        statements
+            .iter()
+            .map(|stmt| self.print_stmt(stmt) + "\n")
+            .collect()
+    }
+
+    pub fn print_stmt(&self, stmt: &Stmt) -> String {
+        self.render(&Self::stmt(stmt))
+    }
+
+    pub fn print_expr(&self, expr: &Expr) -> String {
+        self.render(&Self::expr(expr))
+    }
+
+    fn render(&self, node: &Node) -> String {
+        let mut output = String::new();
+        match self.format {
+            AstFormat::SExpr => sexpr(node, &mut output),
+            AstFormat::Tree => tree(node, 0, &mut output),
+        }
+        // the tree view ends each line, including the last
+        if output.ends_with('\n') {
+            output.pop();
+        }
+        output
+    }
+
+    fn stmt(stmt: &Stmt) -> Node {
+        match stmt {
+            Stmt::Block(s) => list("block", s.statements.iter().map(Self::stmt).collect()),
+            Stmt::Class(s) => {
+                let mut children = vec![name(&s.name)];
+                if let Some(superclass) = &s.superclass {
+                    children.push(list("<", vec![name(&superclass.name)]));
+                }
+                children.extend(s.methods.iter().map(Self::function));
+                list("class", children)
+            }
+            Stmt::Expression(s) => list("expr", vec![Self::expr(&s.expression)]),
+            Stmt::Function(s) => Self::function(s),
+            Stmt::If(s) => {
+                let mut children = vec![Self::expr(&s.condition), Self::stmt(&s.then_branch)];
+                if let Some(else_branch) = &s.else_branch {
+                    children.push(Self::stmt(else_branch));
+                }
+                list("if", children)
+            }
+            Stmt::Print(s) => list("print", vec![Self::expr(&s.expression)]),
+            Stmt::Return(s) => list("return", s.value.iter().map(Self::expr).collect()),
+            Stmt::Var(s) => {
+                let mut children = vec![name(&s.name)];
+                children.extend(s.initializer.iter().map(Self::expr));
+                list("var", children)
+            }
+            Stmt::While(s) => list("while", vec![Self::expr(&s.condition), Self::stmt(&s.body)]),
+        }
+    }
+
+    fn function(function: &FunctionStmt) -> Node {
+        let mut children = vec![
+            name(&function.name),
+            list("params", function.params.iter().map(name).collect()),
+        ];
+        children.extend(function.body.iter().map(Self::stmt));
+        list("fun", children)
+    }
+
+    fn expr(expr: &Expr) -> Node {
+        match expr {
+            Expr::Assign(e) => list("=", vec![name(&e.name), Self::expr(&e.value)]),
+            Expr::Binary(e) => list(
+                &e.operator.lexeme,
+                vec![Self::expr(&e.left), Self::expr(&e.right)],
+            ),
+            Expr::Call(e) => {
+                let mut children = vec![Self::expr(&e.callee)];
+                children.extend(e.arguments.iter().map(Self::expr));
+                list("call", children)
+            }
+            Expr::CompoundAssign(e) => list(
+                &e.operator.lexeme,
+                vec![Self::expr(&e.target), Self::expr(&e.value)],
+            ),
+            Expr::Get(e) => list("get", vec![Self::expr(&e.object), name(&e.name)]),
+            Expr::Grouping(e) => list("group", vec![Self::expr(&e.expression)]),
+            Expr::Interpolation(e) => list("interpolate", e.parts.iter().map(Self::expr).collect()),
+            Expr::Literal(e) => Node::Atom(match &e.value {
+                Literal::String(s) => format!("{:?}", s),
+                Literal::Number(n) => n.to_string(),
+                Literal::Boolean(b) => b.to_string(),
+                Literal::Nil => "nil".to_string(),
+            }),
+            Expr::Logical(e) => list(
+                &e.operator.lexeme,
+                vec![Self::expr(&e.left), Self::expr(&e.right)],
+            ),
+            Expr::Set(e) => list(
+                "set",
+                vec![Self::expr(&e.object), name(&e.name), Self::expr(&e.value)],
+            ),
+            Expr::Super(e) => list("super", vec![name(&e.method)]),
+            Expr::This(_) => Node::Atom("this".to_string()),
+            Expr::Unary(e) => list(&e.operator.lexeme, vec![Self::expr(&e.right)]),
+            Expr::Update(e) => {
+                let operator = match e.prefix {
+                    true => e.operator.lexeme.to_string(),
+                    false => format!("post{}", e.operator.lexeme),
+                };
+                list(&operator, vec![Self::expr(&e.target)])
+            }
+            Expr::Variable(e) => name(&e.name),
+        }
+    }
+}
+
+fn name(token: &Token) -> Node {
+    Node::Atom(token.lexeme.to_string())
+}
+
+fn list(head: &str, children: Vec<Node>) -> Node {
+    Node::List(head.to_string(), children)
+}
+
+fn sexpr(node: &Node, output: &mut String) {
+    match node {
+        Node::Atom(atom) => output.push_str(atom),
+        Node::List(head, children) => {
+            output.push('(');
+            output.push_str(head);
+            for child in children {
+                output.push(' ');
+                sexpr(child, output);
+            }
+            output.push(')');
+        }
+    }
+}
+
+fn tree(node: &Node, depth: usize, output: &mut String) {
+    output.push_str(&"  ".repeat(depth));
+    match node {
+        Node::Atom(atom) => {
+            output.push_str(atom);
+            output.push('\n');
+        }
+        Node::List(head, children) => {
+            output.push_str(head);
+            output.push('\n');
+            for child in children {
+                tree(child, depth + 1, output);
+            }
+        }
+    }
+}
@@ -0,0 +1,484 @@
+use std::{fmt, iter::Peekable};
+
+use crate::{
+    scanner::Scanner,
+    token::{Span, Token, TokenType},
+};
+
+/// Lossless concrete syntax tree. Unlike the AST it keeps every token of the
+/// source along with its trivia, nothing is desugared and input that doesn't
+/// parse is kept in `Error` nodes, so printing the tree reproduces the source
+/// byte-for-byte.
+pub struct SyntaxTree<'a> {
+    source: &'a str,
+    root: SyntaxNode,
+}
+
+impl<'a> SyntaxTree<'a> {
+    /// Builds the tree for any source, valid or not. Errors are not reported,
+    /// run the source through the `Parser` to find them.
+    pub fn parse(source: &'a str) -> Self {
+        let root = CstParser::new(Scanner::lossless(source)).program();
+        Self { source, root }
+    }
+
+    pub fn root(&self) -> &SyntaxNode {
+        &self.root
+    }
+
+    /// Source text covered by `span`, e.g. the raw text of a token or trivia.
+    pub fn text(&self, span: Span) -> &'a str {
+        &self.source[span.start..span.end]
+    }
+}
+
+impl fmt::Display for SyntaxTree<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in self.root.tokens() {
+            for trivia in &token.trivia {
+                f.write_str(self.text(trivia.span))?;
+            }
+            f.write_str(self.text(token.span))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    Program,
+
+    // declarations
+    ClassDecl,
+    FunDecl,
+    Function,
+    Parameters,
+    VarDecl,
+
+    // statements
+    Block,
+    ExprStmt,
+    ForStmt,
+    IfStmt,
+    PrintStmt,
+    ReturnStmt,
+    WhileStmt,
+
+    // expressions
+    AssignExpr,
+    BinaryExpr,
+    CallExpr,
+    Arguments,
+    GetExpr,
+    GroupingExpr,
+    InterpolationExpr,
+    PostfixExpr,
+    PrimaryExpr,
+    UnaryExpr,
+
+    // tokens that don't fit the grammar where they appear
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(Token),
+}
+
+impl SyntaxNode {
+    pub fn new(kind: SyntaxKind, children: Vec<SyntaxElement>) -> Self {
+        Self { kind, children }
+    }
+
+    /// Every token in the node, in source order.
+    pub fn tokens(&self) -> Vec<&Token> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'t>(&'t self, tokens: &mut Vec<&'t Token>) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.collect_tokens(tokens),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+}
+
+// binding power of unary operators, above every binary operator
+const PREFIX_POWER: u8 = 15;
+
+// left and right binding powers of binary operators, the lower the power the
+// looser the operator binds
+fn infix_power(token_type: TokenType) -> Option<(u8, u8, SyntaxKind)> {
+    let (left, right, kind) = match token_type {
+        TokenType::Equal
+        | TokenType::PlusEqual
+        | TokenType::MinusEqual
+        | TokenType::StarEqual
+        | TokenType::SlashEqual => (2, 1, SyntaxKind::AssignExpr),
+        TokenType::Or => (3, 4, SyntaxKind::BinaryExpr),
+        TokenType::And => (5, 6, SyntaxKind::BinaryExpr),
+        TokenType::BangEqual | TokenType::EqualEqual => (7, 8, SyntaxKind::BinaryExpr),
+        TokenType::Greater | TokenType::GreaterEqual | TokenType::Less | TokenType::LessEqual => {
+            (9, 10, SyntaxKind::BinaryExpr)
+        }
+        TokenType::Minus | TokenType::Plus => (11, 12, SyntaxKind::BinaryExpr),
+        TokenType::Slash | TokenType::Star | TokenType::Percent => (13, 14, SyntaxKind::BinaryExpr),
+        _ => return None,
+    };
+    Some((left, right, kind))
+}
+
+// follows the grammar of the `Parser`, but never fails: missing tokens are
+// left out of the tree and unexpected ones wrapped in `Error` nodes
+struct CstParser<'a> {
+    tokens: Peekable<Scanner<'a>>,
+    // tokens consumed so far, used to make sure every loop makes progress
+    consumed: usize,
+}
+
+impl<'a> CstParser<'a> {
+    fn new(scanner: Scanner<'a>) -> Self {
+        Self {
+            tokens: scanner.peekable(),
+            consumed: 0,
+        }
+    }
+
+    fn program(&mut self) -> SyntaxNode {
+        let mut children = Vec::new();
+        while !self.is_at_end() {
+            self.item(&mut children, Self::declaration);
+        }
+        // the `Eof` token carries the trivia at the end of the source
+        if let Some(eof) = self.tokens.next() {
+            children.push(SyntaxElement::Token(eof));
+        }
+        SyntaxNode::new(SyntaxKind::Program, children)
+    }
+
+    // parses one element of a list, skipping a token as an error when the
+    // element can't start there
+    fn item(&mut self, children: &mut Vec<SyntaxElement>, parse: fn(&mut Self) -> SyntaxNode) {
+        let before = self.consumed;
+        let node = parse(self);
+        if self.consumed > before {
+            children.push(SyntaxElement::Node(node));
+        } else {
+            let mut skipped = Vec::new();
+            self.bump(&mut skipped);
+            children.push(SyntaxElement::Node(SyntaxNode::new(
+                SyntaxKind::Error,
+                skipped,
+            )));
+        }
+    }
+
+    fn declaration(&mut self) -> SyntaxNode {
+        match self.peek() {
+            TokenType::Class => self.class_declaration(),
+            TokenType::Fun => {
+                let mut children = Vec::new();
+                self.bump(&mut children);
+                children.push(SyntaxElement::Node(self.function()));
+                SyntaxNode::new(SyntaxKind::FunDecl, children)
+            }
+            TokenType::Var => self.var_declaration(),
+            _ => self.statement(),
+        }
+    }
+
+    fn class_declaration(&mut self) -> SyntaxNode {
+        let mut children = Vec::new();
+        self.bump(&mut children);
+        self.eat(TokenType::Identifer, &mut children);
+        if self.eat(TokenType::Less, &mut children) {
+            self.eat(TokenType::Identifer, &mut children);
+        }
+        if self.eat(TokenType::LeftBrace, &mut children) {
+            while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+                self.item(&mut children, Self::function);
+            }
+            self.eat(TokenType::RightBrace, &mut children);
+        }
+        SyntaxNode::new(SyntaxKind::ClassDecl, children)
+    }
+
+    fn function(&mut self) -> SyntaxNode {
+        let mut children = Vec::new();
+        self.eat(TokenType::Identifer, &mut children);
+        if self.check(TokenType::LeftParens) {
+            let mut parameters = Vec::new();
+            self.bump(&mut parameters);
+            while !self.check(TokenType::RightParens)
+                && !self.check(TokenType::LeftBrace)
+                && !self.is_at_end()
+            {
+                self.bump(&mut parameters);
+            }
+            self.eat(TokenType::RightParens, &mut parameters);
+            children.push(SyntaxElement::Node(SyntaxNode::new(
+                SyntaxKind::Parameters,
+                parameters,
+            )));
+        }
+        if self.check(TokenType::LeftBrace) {
+            children.push(SyntaxElement::Node(self.block()));
+        }
+        SyntaxNode::new(SyntaxKind::Function, children)
+    }
+
+    fn var_declaration(&mut self) -> SyntaxNode {
+        let mut children = Vec::new();
+        self.bump(&mut children);
+        self.eat(TokenType::Identifer, &mut children);
+        if self.eat(TokenType::Equal, &mut children) {
+            self.expression(&mut children);
+        }
+        self.eat(TokenType::SemiColon, &mut children);
+        SyntaxNode::new(SyntaxKind::VarDecl, children)
+    }
+
+    fn statement(&mut self) -> SyntaxNode {
+        let mut children = Vec::new();
+        let kind = match self.peek() {
+            TokenType::LeftBrace => return self.block(),
+            TokenType::For => {
+                self.bump(&mut children);
+                self.eat(TokenType::LeftParens, &mut children);
+                match self.peek() {
+                    TokenType::SemiColon => self.bump(&mut children),
+                    TokenType::Var => children.push(SyntaxElement::Node(self.var_declaration())),
+                    _ => children.push(SyntaxElement::Node(self.expression_statement())),
+                }
+                if !self.check(TokenType::SemiColon) {
+                    self.expression(&mut children);
+                }
+                self.eat(TokenType::SemiColon, &mut children);
+                if !self.check(TokenType::RightParens) {
+                    self.expression(&mut children);
+                }
+                self.eat(TokenType::RightParens, &mut children);
+                self.body(&mut children);
+                SyntaxKind::ForStmt
+            }
+            TokenType::If => {
+                self.bump(&mut children);
+                self.condition(&mut children);
+                self.body(&mut children);
+                if self.eat(TokenType::Else, &mut children) {
+                    self.body(&mut children);
+                }
+                SyntaxKind::IfStmt
+            }
+            TokenType::While => {
+                self.bump(&mut children);
+                self.condition(&mut children);
+                self.body(&mut children);
+                SyntaxKind::WhileStmt
+            }
+            TokenType::Print => {
+                self.bump(&mut children);
+                self.expression(&mut children);
+                self.eat(TokenType::SemiColon, &mut children);
+                SyntaxKind::PrintStmt
+            }
+            TokenType::Return => {
+                self.bump(&mut children);
+                if !self.check(TokenType::SemiColon) {
+                    self.expression(&mut children);
+                }
+                self.eat(TokenType::SemiColon, &mut children);
+                SyntaxKind::ReturnStmt
+            }
+            _ => return self.expression_statement(),
+        };
+        SyntaxNode::new(kind, children)
+    }
+
+    fn block(&mut self) -> SyntaxNode {
+        let mut children = Vec::new();
+        self.bump(&mut children);
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            self.item(&mut children, Self::declaration);
+        }
+        self.eat(TokenType::RightBrace, &mut children);
+        SyntaxNode::new(SyntaxKind::Block, children)
+    }
+
+    fn expression_statement(&mut self) -> SyntaxNode {
+        let mut children = Vec::new();
+        self.expression(&mut children);
+        self.eat(TokenType::SemiColon, &mut children);
+        SyntaxNode::new(SyntaxKind::ExprStmt, children)
+    }
+
+    // parenthesized condition of `if` and `while`
+    fn condition(&mut self, children: &mut Vec<SyntaxElement>) {
+        self.eat(TokenType::LeftParens, children);
+        self.expression(children);
+        self.eat(TokenType::RightParens, children);
+    }
+
+    // statement nested in another, left out if there isn't one
+    fn body(&mut self, children: &mut Vec<SyntaxElement>) {
+        let before = self.consumed;
+        let statement = self.statement();
+        if self.consumed > before {
+            children.push(SyntaxElement::Node(statement));
+        }
+    }
+
+    fn expression(&mut self, children: &mut Vec<SyntaxElement>) {
+        if let Some(expression) = self.expression_with_power(0) {
+            children.push(SyntaxElement::Node(expression));
+        }
+    }
+
+    // parses an expression containing only operators binding tighter than
+    // `min_power`, or nothing if no expression starts at the current token
+    fn expression_with_power(&mut self, min_power: u8) -> Option<SyntaxNode> {
+        let mut left = self.prefix()?;
+        loop {
+            let mut children = vec![SyntaxElement::Node(left)];
+            let kind = match self.peek() {
+                TokenType::LeftParens => {
+                    children.push(SyntaxElement::Node(self.arguments()));
+                    SyntaxKind::CallExpr
+                }
+                TokenType::Dot => {
+                    self.bump(&mut children);
+                    self.eat(TokenType::Identifer, &mut children);
+                    SyntaxKind::GetExpr
+                }
+                TokenType::PlusPlus | TokenType::MinusMinus => {
+                    self.bump(&mut children);
+                    SyntaxKind::PostfixExpr
+                }
+                token_type => match infix_power(token_type) {
+                    Some((left_power, right_power, kind)) if left_power >= min_power => {
+                        self.bump(&mut children);
+                        if let Some(right) = self.expression_with_power(right_power) {
+                            children.push(SyntaxElement::Node(right));
+                        }
+                        kind
+                    }
+                    _ => match children.pop() {
+                        Some(SyntaxElement::Node(left)) => return Some(left),
+                        _ => unreachable!(),
+                    },
+                },
+            };
+            left = SyntaxNode::new(kind, children);
+        }
+    }
+
+    fn prefix(&mut self) -> Option<SyntaxNode> {
+        let mut children = Vec::new();
+        let kind = match self.peek() {
+            TokenType::Bang | TokenType::Minus | TokenType::PlusPlus | TokenType::MinusMinus => {
+                self.bump(&mut children);
+                if let Some(operand) = self.expression_with_power(PREFIX_POWER) {
+                    children.push(SyntaxElement::Node(operand));
+                }
+                SyntaxKind::UnaryExpr
+            }
+            TokenType::LeftParens => {
+                self.bump(&mut children);
+                self.expression(&mut children);
+                self.eat(TokenType::RightParens, &mut children);
+                SyntaxKind::GroupingExpr
+            }
+            TokenType::Interpolation => {
+                loop {
+                    self.bump(&mut children);
+                    self.expression(&mut children);
+                    if !self.check(TokenType::Interpolation) {
+                        break;
+                    }
+                }
+                self.eat(TokenType::String, &mut children);
+                SyntaxKind::InterpolationExpr
+            }
+            TokenType::Number
+            | TokenType::String
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Nil
+            | TokenType::This
+            | TokenType::Super
+            | TokenType::Identifer => {
+                self.bump(&mut children);
+                SyntaxKind::PrimaryExpr
+            }
+            _ => return None,
+        };
+        Some(SyntaxNode::new(kind, children))
+    }
+
+    fn arguments(&mut self) -> SyntaxNode {
+        let mut children = Vec::new();
+        self.bump(&mut children);
+        while !self.check(TokenType::RightParens)
+            && !self.check(TokenType::SemiColon)
+            && !self.is_at_end()
+        {
+            self.item(&mut children, |parser| {
+                let mut argument = Vec::new();
+                parser.expression(&mut argument);
+                match argument.pop() {
+                    Some(SyntaxElement::Node(node)) => node,
+                    _ => SyntaxNode::new(SyntaxKind::Error, argument),
+                }
+            });
+            self.eat(TokenType::Comma, &mut children);
+        }
+        self.eat(TokenType::RightParens, &mut children);
+        SyntaxNode::new(SyntaxKind::Arguments, children)
+    }
+
+    fn peek(&mut self) -> TokenType {
+        self.tokens
+            .peek()
+            .map_or(TokenType::Eof, |token| token.token_type)
+    }
+
+    fn check(&mut self, token_type: TokenType) -> bool {
+        self.peek() == token_type
+    }
+
+    fn is_at_end(&mut self) -> bool {
+        self.check(TokenType::Eof)
+    }
+
+    // moves the current token into `children`, `Eof` is only consumed by
+    // `program`
+    fn bump(&mut self, children: &mut Vec<SyntaxElement>) {
+        if self.is_at_end() {
+            return;
+        }
+        if let Some(token) = self.tokens.next() {
+            self.consumed += 1;
+            children.push(SyntaxElement::Token(token));
+        }
+    }
+
+    fn eat(&mut self, token_type: TokenType, children: &mut Vec<SyntaxElement>) -> bool {
+        if self.check(token_type) {
+            self.bump(children);
+            true
+        } else {
+            false
+        }
+    }
+}
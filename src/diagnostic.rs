@@ -0,0 +1,180 @@
+use std::fmt::Write;
+
+use crate::{error::LoxError, token::Span};
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders `LoxError`s as rustc-style diagnostics: a labelled header, the
+/// location, and the offending source line with the span underlined.
+///
+/// ```text
+/// error[E0002]: Expect expression.
+///  --> script.lox:3:9
+///   |
+/// 3 | print (1;
+///   |         ^
+/// ```
+pub struct DiagnosticRenderer<'a> {
+    name: &'a str,
+    source: &'a str,
+    color: bool,
+}
+
+impl<'a> DiagnosticRenderer<'a> {
+    pub fn new(name: &'a str, source: &'a str, color: bool) -> Self {
+        DiagnosticRenderer {
+            name,
+            source,
+            color,
+        }
+    }
+
+    pub fn render(&self, error: &LoxError) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}: {}",
+            self.paint(RED, &format!("error[{}]", error.code())),
+            self.paint(BOLD, &error.message())
+        );
+
+        // errors without a span, like failing to read the file, still say
+        // which file they're about
+        match error.span() {
+            Some(span) => self.render_snippet(&mut out, span),
+            None => {
+                let _ = writeln!(out, " {} {}", self.paint(BLUE, "-->"), self.name);
+            }
+        }
+
+        out.push('\n');
+        out
+    }
+
+    /// Renders the error as a single-line JSON object for tools to consume.
+    ///
+    /// ```text
+    /// {"file":"script.lox","span":{"start":14,"end":15,"line":3,"column":9},"severity":"error","code":"E0002","message":"Expect expression."}
+    /// ```
+    pub fn render_json(&self, error: &LoxError) -> String {
+        self.json(error.span(), "error", Some(error.code()), &error.message())
+    }
+
+    /// Renders a note about the whole file, like `fmt --check` finding it
+    /// unformatted, in the same shape as `render_json`.
+    ///
+    /// ```text
+    /// {"file":"script.lox","span":null,"severity":"warning","code":null,"message":"Would reformat."}
+    /// ```
+    pub fn render_json_note(&self, message: &str) -> String {
+        self.json(None, "warning", None, message)
+    }
+
+    fn json(
+        &self,
+        span: Option<Span>,
+        severity: &str,
+        code: Option<&str>,
+        message: &str,
+    ) -> String {
+        let span = match span {
+            Some(span) => format!(
+                "{{\"start\":{},\"end\":{},\"line\":{},\"column\":{}}}",
+                span.start, span.end, span.line, span.column
+            ),
+            None => "null".to_string(),
+        };
+
+        format!(
+            "{{\"file\":{},\"span\":{},\"severity\":{},\"code\":{},\"message\":{}}}\n",
+            json_string(self.name),
+            span,
+            json_string(severity),
+            code.map_or("null".to_string(), json_string),
+            json_string(message)
+        )
+    }
+
+    fn render_snippet(&self, out: &mut String, span: Span) {
+        let line_number = span.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let text = self
+            .source
+            .lines()
+            .nth(span.line.saturating_sub(1) as usize)
+            .unwrap_or("");
+
+        // underline from the start column to the end of the span, stopping at
+        // the end of the first line for spans that cover several lines
+        let offset = span.column.saturating_sub(1) as usize;
+        let length = self
+            .source
+            .get(span.start..span.end)
+            .and_then(|s| s.lines().next())
+            .map(|s| s.chars().count())
+            .unwrap_or(0)
+            .max(1);
+        let padding: String = text
+            .chars()
+            .take(offset)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        let _ = writeln!(
+            out,
+            "{}{} {}:{}:{}",
+            gutter,
+            self.paint(BLUE, "-->"),
+            self.name,
+            span.line,
+            span.column
+        );
+        let _ = writeln!(out, "{} {}", gutter, self.paint(BLUE, "|"));
+        let _ = writeln!(
+            out,
+            "{} {} {}",
+            self.paint(BLUE, &line_number),
+            self.paint(BLUE, "|"),
+            text
+        );
+        let _ = writeln!(
+            out,
+            "{} {} {}{}",
+            gutter,
+            self.paint(BLUE, "|"),
+            padding,
+            self.paint(RED, &"^".repeat(length))
+        );
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,98 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{error::LoxError, interpreter::RuntimeValue, token::Token};
+
+pub struct Environment {
+    values: HashMap<String, RuntimeValue>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Environment {
+            values: HashMap::new(),
+            enclosing: None,
+        }
+    }
+
+    pub fn new_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Environment {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+
+    pub fn define(&mut self, name: String, value: RuntimeValue) {
+        self.values.insert(name, value);
+    }
+
+    pub fn get(&self, name: &Token) -> Result<RuntimeValue, LoxError> {
+        if let Some(value) = self.values.get(&*name.lexeme) {
+            return Ok(value.clone());
+        }
+
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow().get(name),
+            None => Err(LoxError::runtime(
+                name,
+                &format!("Undefined variable '{}'.", name.lexeme),
+            )),
+        }
+    }
+
+    // distances come from the resolver, so the variable is known to exist
+    pub fn get_at(env: &Rc<RefCell<Environment>>, distance: usize, name: &str) -> RuntimeValue {
+        Environment::ancestor(env, distance)
+            .borrow()
+            .values
+            .get(name)
+            .cloned()
+            .unwrap_or(RuntimeValue::Nil)
+    }
+
+    pub fn assign_at(
+        env: &Rc<RefCell<Environment>>,
+        distance: usize,
+        name: &Token,
+        value: RuntimeValue,
+    ) {
+        Environment::ancestor(env, distance)
+            .borrow_mut()
+            .values
+            .insert(name.lexeme.to_string(), value);
+    }
+
+    fn ancestor(env: &Rc<RefCell<Environment>>, distance: usize) -> Rc<RefCell<Environment>> {
+        let mut environment = Rc::clone(env);
+        for _ in 0..distance {
+            let enclosing = environment
+                .borrow()
+                .enclosing
+                .clone()
+                .expect("resolved scope depth exceeds environment chain");
+            environment = enclosing;
+        }
+        environment
+    }
+
+    pub fn assign(&mut self, name: &Token, value: RuntimeValue) -> Result<(), LoxError> {
+        if let Some(slot) = self.values.get_mut(&*name.lexeme) {
+            *slot = value;
+            return Ok(());
+        }
+
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+            None => Err(LoxError::runtime(
+                name,
+                &format!("Undefined variable '{}'.", name.lexeme),
+            )),
+        }
+    }
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,26 +1,83 @@
 use std::{fmt, io};
 
-use crate::token::{Token, TokenType};
+use crate::{
+    interpreter::RuntimeValue,
+    token::{Span, Token, TokenType},
+};
 
 pub enum LoxError {
-    ParserError(String, usize, Token),
+    ScannerError(String, Span),
+    ParserError(String, Token),
+    ResolverError(String, Token),
     Io(io::Error),
-    RuntimeError(),
+    RuntimeError(String, Token),
+    // not a real error, used to unwind the call stack on `return`
+    Return(RuntimeValue),
 }
 
 impl fmt::Display for LoxError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            LoxError::ParserError(ref s, ref l, ref t) => {
+            LoxError::ScannerError(ref s, ref span) => {
+                write!(
+                    f,
+                    "[line {}, column {}] Error: {}",
+                    span.line, span.column, s
+                )
+            }
+            LoxError::ParserError(ref s, ref t) => {
                 if t.token_type == TokenType::Eof {
-                    write!(f, "{} at end {}", l, s)
+                    write!(f, "{} at end {}", t.span.line, s)
                 } else {
-                    write!(f, "{} at '{}' {}", l, t.lexeme, s)
+                    write!(f, "{} at '{}' {}", t.span.line, t.lexeme, s)
                 }
             }
+            LoxError::ResolverError(ref s, ref t) => {
+                write!(f, "{} at '{}' {}", t.span.line, t.lexeme, s)
+            }
             LoxError::Io(ref err) => write!(f, "IO Error: {}", err),
-            // TODO(fill in error info)
-            LoxError::RuntimeError() => write!(f, "Runtime Error: "),
+            LoxError::RuntimeError(ref s, ref t) => write!(f, "{}\n[line {}]", s, t.span.line),
+            LoxError::Return(_) => write!(f, "Can't return from top-level code."),
+        }
+    }
+}
+
+impl LoxError {
+    pub fn runtime(token: &Token, msg: &str) -> LoxError {
+        LoxError::RuntimeError(msg.into(), token.clone())
+    }
+
+    /// Stable identifier for the kind of error, shown in diagnostics.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LoxError::ScannerError(..) => "E0001",
+            LoxError::ParserError(..) => "E0002",
+            LoxError::ResolverError(..) => "E0003",
+            LoxError::RuntimeError(..) | LoxError::Return(_) => "E0004",
+            LoxError::Io(_) => "E0005",
+        }
+    }
+
+    /// Location in the source the error points at, if it has one.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            LoxError::ScannerError(_, span) => Some(*span),
+            LoxError::ParserError(_, t)
+            | LoxError::ResolverError(_, t)
+            | LoxError::RuntimeError(_, t) => Some(t.span),
+            LoxError::Io(_) | LoxError::Return(_) => None,
+        }
+    }
+
+    /// The error message without any location information.
+    pub fn message(&self) -> String {
+        match self {
+            LoxError::ScannerError(s, _)
+            | LoxError::ParserError(s, _)
+            | LoxError::ResolverError(s, _)
+            | LoxError::RuntimeError(s, _) => s.clone(),
+            LoxError::Io(err) => err.to_string(),
+            LoxError::Return(_) => "Can't return from top-level code.".into(),
         }
     }
 }
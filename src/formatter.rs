@@ -0,0 +1,737 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    cst::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxTree},
+    error::LoxError,
+    parser::Parser,
+    scanner::Scanner,
+    token::{Token, TokenType, TriviaKind},
+};
+
+const MAX_WIDTH: usize = 80;
+const INDENT: usize = 2;
+
+/// Re-emits a program in the canonical style: one statement per line, two
+/// space indentation, opening braces on the line of their statement, spaces
+/// around binary operators, and long expressions wrapped to fit in 80 columns.
+/// Comments are kept, as are single blank lines between statements.
+///
+/// Source that doesn't scan or parse is left alone and its errors returned.
+pub fn format(source: &str) -> Result<String, Vec<LoxError>> {
+    let mut scanner = Scanner::new(source);
+    let parsed = Parser::new(&mut scanner).parse();
+    let mut errors = scanner.take_errors();
+    if let Err(parse_errors) = parsed {
+        errors.extend(parse_errors);
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let tree = SyntaxTree::parse(source);
+    let doc = Formatter::new(&tree).program(tree.root());
+    Ok(Printer::new().print(&doc))
+}
+
+// layout of the output, decided by the printer: a `Group` is printed on a
+// single line when it fits, otherwise each `Line` and `SoftLine` directly in
+// it breaks
+enum Doc {
+    Text(String),
+    // a space, or a line break
+    Line,
+    // nothing, or a line break
+    SoftLine,
+    // always a line break
+    HardLine,
+    // a line break unless already at the start of a line
+    FreshLine,
+    // printed at the end of the line, used for trailing line comments
+    LineSuffix(String),
+    // comments before a token, which don't keep a group starting with them
+    // from fitting
+    Leading(Box<Doc>),
+    Group(Box<Doc>),
+    Indent(Box<Doc>),
+    Concat(Vec<Doc>),
+}
+
+fn text(s: &str) -> Doc {
+    Doc::Text(s.into())
+}
+
+fn group(docs: Vec<Doc>) -> Doc {
+    Doc::Group(Box::new(Doc::Concat(docs)))
+}
+
+fn indent(docs: Vec<Doc>) -> Doc {
+    Doc::Indent(Box::new(Doc::Concat(docs)))
+}
+
+struct Comment<'a> {
+    text: &'a str,
+    blank_line_before: bool,
+    newline_after: bool,
+}
+
+// comments on their own lines before a token, and whether a blank line
+// separates the token from whatever precedes it
+#[derive(Default)]
+struct Leading<'a> {
+    comments: Vec<Comment<'a>>,
+    blank_line_before: bool,
+}
+
+impl Leading<'_> {
+    // whether a blank line precedes the first thing printed for the token
+    fn starts_with_blank_line(&self) -> bool {
+        match self.comments.first() {
+            Some(comment) => comment.blank_line_before,
+            None => self.blank_line_before,
+        }
+    }
+}
+
+struct Formatter<'a> {
+    tree: &'a SyntaxTree<'a>,
+    // keyed by the start of the token they belong to
+    leading: HashMap<usize, Leading<'a>>,
+    trailing: HashMap<usize, Vec<&'a str>>,
+    // `(` directly followed by `)`
+    empty_parens: HashSet<usize>,
+    // tokens ending a line with a line comment, directly or through the
+    // punctuation that follows them
+    before_line_comment: HashSet<usize>,
+}
+
+impl<'a> Formatter<'a> {
+    // the scanner attaches all trivia to the following token, but comments
+    // on the same line as the previous token are printed after it instead.
+    // nothing but punctuation may follow a line comment, so comments after
+    // that stay with the following token
+    fn new(tree: &'a SyntaxTree<'a>) -> Self {
+        let mut leading = HashMap::new();
+        let mut trailing: HashMap<usize, Vec<&str>> = HashMap::new();
+        let mut previous: Option<&Token> = None;
+        let mut after_line_comment = false;
+        let mut empty_parens = HashSet::new();
+        let mut before_line_comment = HashSet::new();
+        for token in tree.root().tokens() {
+            if let Some(previous) = previous.filter(|p| p.token_type == TokenType::LeftParens) {
+                if token.token_type == TokenType::RightParens {
+                    empty_parens.insert(previous.span.start);
+                }
+            }
+            let mut token_leading = Leading::default();
+            let mut after_newline = previous.is_none();
+            let mut newlines = 0;
+            for trivia in token.trivia.iter() {
+                let text = tree.text(trivia.span);
+                match trivia.kind {
+                    TriviaKind::Whitespace => {
+                        newlines += text.matches('\n').count();
+                        after_newline |= newlines > 0;
+                        if let Some(comment) = token_leading.comments.last_mut() {
+                            comment.newline_after |= newlines > 0;
+                        }
+                    }
+                    _ if !after_newline && !after_line_comment => {
+                        if let Some(previous) = previous {
+                            trailing.entry(previous.span.start).or_default().push(text);
+                        }
+                        after_line_comment = trivia.kind == TriviaKind::LineComment;
+                    }
+                    _ => {
+                        token_leading.comments.push(Comment {
+                            text,
+                            blank_line_before: newlines > 1,
+                            newline_after: trivia.kind == TriviaKind::LineComment,
+                        });
+                        newlines = 0;
+                    }
+                }
+            }
+            token_leading.blank_line_before = newlines > 1;
+            if let Some(previous) = previous.filter(|_| after_line_comment) {
+                before_line_comment.insert(previous.span.start);
+            }
+            after_line_comment &=
+                token_leading.comments.is_empty() && is_punctuation(tree.text(token.span));
+            leading.insert(token.span.start, token_leading);
+            previous = Some(token);
+        }
+
+        Self {
+            tree,
+            leading,
+            trailing,
+            empty_parens,
+            before_line_comment,
+        }
+    }
+
+    fn program(&self, program: &SyntaxNode) -> Doc {
+        let items: Vec<&SyntaxNode> = nodes(program).collect();
+        match tokens(program).last() {
+            Some(eof) => self.lines(&items, eof, true),
+            None => Doc::Concat(Vec::new()),
+        }
+    }
+
+    // one item per line keeping single blank lines between them, followed by
+    // the comments before the token closing the list
+    fn lines(&self, items: &[&SyntaxNode], end: &Token, top_level: bool) -> Doc {
+        let mut docs = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 || !top_level {
+                docs.push(Doc::HardLine);
+            }
+            let blank_line = first_token(item)
+                .is_some_and(|token| self.leading_of(token).starts_with_blank_line());
+            if i > 0 && blank_line {
+                docs.push(Doc::HardLine);
+            }
+            docs.push(self.node(item));
+        }
+        for comment in &self.leading_of(end).comments {
+            if !docs.is_empty() || !top_level {
+                docs.push(Doc::HardLine);
+            }
+            if comment.blank_line_before && !docs.is_empty() {
+                docs.push(Doc::HardLine);
+            }
+            docs.push(text(comment.text));
+        }
+        Doc::Concat(docs)
+    }
+
+    // `{ ... }` around a list of items, the braces on the lines of the first
+    // and last item
+    fn braced(&self, open: &Token, items: &[&SyntaxNode], close: &Token) -> Doc {
+        let body = self.lines(items, close, false);
+        let is_empty = items.is_empty() && self.leading_of(close).comments.is_empty();
+        if is_empty {
+            Doc::Concat(vec![self.token(open), self.bare(close)])
+        } else {
+            Doc::Concat(vec![
+                self.token(open),
+                indent(vec![body]),
+                Doc::HardLine,
+                self.bare(close),
+            ])
+        }
+    }
+
+    fn node(&self, node: &SyntaxNode) -> Doc {
+        match node.kind {
+            SyntaxKind::ClassDecl => self.class_declaration(node),
+            SyntaxKind::FunDecl => self.spaced(node),
+            SyntaxKind::Function => Doc::Concat(
+                node.children
+                    .iter()
+                    .map(|child| match child {
+                        SyntaxElement::Node(block) if block.kind == SyntaxKind::Block => {
+                            Doc::Concat(vec![text(" "), self.node(block)])
+                        }
+                        child => self.element(child),
+                    })
+                    .collect(),
+            ),
+            SyntaxKind::Parameters | SyntaxKind::Arguments => self.list(node),
+            SyntaxKind::VarDecl
+            | SyntaxKind::PrintStmt
+            | SyntaxKind::ReturnStmt
+            | SyntaxKind::ExprStmt
+            | SyntaxKind::AssignExpr => self.spaced(node),
+            SyntaxKind::Block => {
+                let mut tokens = tokens(node);
+                let items: Vec<&SyntaxNode> = nodes(node).collect();
+                match (tokens.next(), tokens.next()) {
+                    (Some(open), Some(close)) => self.braced(open, &items, close),
+                    _ => self.spaced(node),
+                }
+            }
+            SyntaxKind::IfStmt | SyntaxKind::WhileStmt | SyntaxKind::ForStmt => {
+                self.control_flow(node)
+            }
+            SyntaxKind::BinaryExpr => {
+                let mut docs = Vec::new();
+                for child in &node.children {
+                    match child {
+                        SyntaxElement::Token(operator) => {
+                            docs.push(text(" "));
+                            docs.push(self.token(operator));
+                        }
+                        SyntaxElement::Node(operand) if docs.is_empty() => {
+                            docs.push(self.node(operand));
+                        }
+                        SyntaxElement::Node(operand) => {
+                            docs.push(indent(vec![Doc::Line, self.node(operand)]));
+                        }
+                    }
+                }
+                group(docs)
+            }
+            SyntaxKind::UnaryExpr => {
+                let mut docs: Vec<Doc> = node.children.iter().map(|c| self.element(c)).collect();
+                // `- -a` must not become the decrement `--a`
+                let operator = tokens(node).next().map(|t| t.token_type);
+                let operand = nodes(node)
+                    .next()
+                    .and_then(first_token)
+                    .map(|t| t.token_type);
+                if operator == Some(TokenType::Minus)
+                    && matches!(operand, Some(TokenType::Minus | TokenType::MinusMinus))
+                {
+                    docs.insert(1, text(" "));
+                }
+                Doc::Concat(docs)
+            }
+            SyntaxKind::InterpolationExpr => Doc::Concat(
+                node.children
+                    .iter()
+                    .map(|child| flatten(self.element(child)))
+                    .collect(),
+            ),
+            _ => Doc::Concat(node.children.iter().map(|c| self.element(c)).collect()),
+        }
+    }
+
+    fn element(&self, element: &SyntaxElement) -> Doc {
+        match element {
+            SyntaxElement::Node(node) => self.node(node),
+            SyntaxElement::Token(token) => self.token(token),
+        }
+    }
+
+    // children separated by single spaces, except before `;`. whatever
+    // follows a line comment is indented as a continuation of the line
+    fn spaced(&self, node: &SyntaxNode) -> Doc {
+        let mut docs = Vec::new();
+        let mut continuation: Option<Vec<Doc>> = None;
+        for (i, child) in node.children.iter().enumerate() {
+            let is_semicolon =
+                matches!(child, SyntaxElement::Token(t) if t.token_type == TokenType::SemiColon);
+            let docs = continuation.as_mut().unwrap_or(&mut docs);
+            if i > 0 && !is_semicolon {
+                docs.push(text(" "));
+            }
+            docs.push(self.element(child));
+            if continuation.is_none() && self.ends_with_line_comment(child) {
+                continuation = Some(Vec::new());
+            }
+        }
+        if let Some(rest) = continuation {
+            docs.push(indent(rest));
+        }
+        Doc::Concat(docs)
+    }
+
+    fn ends_with_line_comment(&self, element: &SyntaxElement) -> bool {
+        let token = match element {
+            SyntaxElement::Token(token) => Some(token),
+            SyntaxElement::Node(node) => last_token(node),
+        };
+        token.is_some_and(|token| self.before_line_comment.contains(&token.span.start))
+    }
+
+    fn class_declaration(&self, node: &SyntaxNode) -> Doc {
+        let mut docs = Vec::new();
+        let mut children = node.children.iter();
+        for child in children.by_ref() {
+            if let SyntaxElement::Token(open) = child {
+                if open.token_type == TokenType::LeftBrace {
+                    let methods: Vec<&SyntaxNode> = nodes(node).collect();
+                    let close = tokens(node).last().unwrap_or(open);
+                    docs.push(text(" "));
+                    docs.push(self.braced(open, &methods, close));
+                    break;
+                }
+            }
+            if !docs.is_empty() {
+                docs.push(text(" "));
+            }
+            docs.push(self.element(child));
+        }
+        Doc::Concat(docs)
+    }
+
+    // `(a, b, c)`, broken into one element per line when too long
+    fn list(&self, node: &SyntaxNode) -> Doc {
+        let (open, rest) = match node.children.split_first() {
+            Some((open, rest)) => (open, rest),
+            None => return Doc::Concat(Vec::new()),
+        };
+        let (close, elements) = match rest.split_last() {
+            Some((SyntaxElement::Token(close), elements))
+                if close.token_type == TokenType::RightParens =>
+            {
+                (Some(close), elements)
+            }
+            _ => (None, rest),
+        };
+
+        let mut inner = vec![Doc::SoftLine];
+        for element in elements {
+            match element {
+                SyntaxElement::Token(comma) if comma.token_type == TokenType::Comma => {
+                    inner.push(self.token(comma));
+                    inner.push(Doc::Line);
+                }
+                element => inner.push(self.element(element)),
+            }
+        }
+        let mut docs = vec![self.element(open)];
+        if !elements.is_empty() {
+            docs.push(indent(inner));
+            docs.push(Doc::SoftLine);
+        }
+        if let Some(close) = close {
+            docs.push(self.token(close));
+        }
+        group(docs)
+    }
+
+    fn control_flow(&self, node: &SyntaxNode) -> Doc {
+        let mut docs = Vec::new();
+        // the previous element, to tell where the header ends and the bodies
+        // begin
+        let mut previous: Option<&SyntaxElement> = None;
+        let mut in_header = false;
+        for child in &node.children {
+            match child {
+                SyntaxElement::Token(token) => match token.token_type {
+                    TokenType::LeftParens => {
+                        docs.push(text(" "));
+                        docs.push(self.token(token));
+                        in_header = true;
+                    }
+                    TokenType::RightParens => {
+                        docs.push(self.token(token));
+                        in_header = false;
+                    }
+                    TokenType::SemiColon => docs.push(self.token(token)),
+                    TokenType::Else => {
+                        let after_block = matches!(
+                            previous,
+                            Some(SyntaxElement::Node(n)) if n.kind == SyntaxKind::Block
+                        );
+                        docs.push(if after_block {
+                            text(" ")
+                        } else {
+                            Doc::HardLine
+                        });
+                        docs.push(self.token(token));
+                    }
+                    _ => docs.push(self.token(token)),
+                },
+                SyntaxElement::Node(inner) if in_header => {
+                    let after_paren = matches!(
+                        previous,
+                        Some(SyntaxElement::Token(t)) if t.token_type == TokenType::LeftParens
+                    );
+                    if !after_paren {
+                        docs.push(text(" "));
+                    }
+                    docs.push(self.node(inner));
+                }
+                SyntaxElement::Node(body) => {
+                    let after_else = matches!(
+                        previous,
+                        Some(SyntaxElement::Token(t)) if t.token_type == TokenType::Else
+                    );
+                    if body.kind == SyntaxKind::Block
+                        || (after_else && body.kind == SyntaxKind::IfStmt)
+                    {
+                        docs.push(text(" "));
+                        docs.push(self.node(body));
+                    } else {
+                        docs.push(group(vec![indent(vec![Doc::Line, self.node(body)])]));
+                    }
+                }
+            }
+            previous = Some(child);
+        }
+        Doc::Concat(docs)
+    }
+
+    // a token with the comments around it
+    fn token(&self, token: &Token) -> Doc {
+        let leading = self.leading_of(token);
+        if leading.comments.is_empty() {
+            return self.bare(token);
+        }
+        let mut docs = vec![Doc::FreshLine];
+        for (i, comment) in leading.comments.iter().enumerate() {
+            if i > 0 && comment.blank_line_before {
+                docs.push(Doc::HardLine);
+            }
+            docs.push(text(comment.text));
+            if comment.newline_after {
+                docs.push(Doc::HardLine);
+            } else {
+                docs.push(text(" "));
+            }
+        }
+        if leading.blank_line_before {
+            docs.push(Doc::HardLine);
+        }
+        Doc::Concat(vec![
+            Doc::Leading(Box::new(Doc::Concat(docs))),
+            self.bare(token),
+        ])
+    }
+
+    // a token with its trailing comments, for when the comments before it
+    // are printed separately. block comments after `(` hug it like the
+    // contents would, as in `f(/* a */ 1)`
+    fn bare(&self, token: &Token) -> Doc {
+        let mut docs = vec![text(self.tree.text(token.span))];
+        let open = token.token_type == TokenType::LeftParens;
+        // whether the last thing printed is a comment hugging `(`
+        let mut hugging = false;
+        for comment in self.trailing.get(&token.span.start).into_iter().flatten() {
+            if comment.starts_with("//") {
+                docs.push(Doc::LineSuffix(format!(" {}", comment)));
+                hugging = false;
+            } else if open {
+                if hugging {
+                    docs.push(text(" "));
+                }
+                docs.push(text(comment));
+                hugging = true;
+            } else {
+                docs.push(Doc::Text(format!(" {}", comment)));
+            }
+        }
+        if hugging && !self.empty_parens.contains(&token.span.start) {
+            docs.push(text(" "));
+        }
+        Doc::Concat(docs)
+    }
+
+    fn leading_of(&self, token: &Token) -> &Leading<'a> {
+        static NONE: Leading = Leading {
+            comments: Vec::new(),
+            blank_line_before: false,
+        };
+        self.leading.get(&token.span.start).unwrap_or(&NONE)
+    }
+}
+
+fn nodes(node: &SyntaxNode) -> impl Iterator<Item = &SyntaxNode> {
+    node.children.iter().filter_map(|child| match child {
+        SyntaxElement::Node(node) => Some(node),
+        SyntaxElement::Token(_) => None,
+    })
+}
+
+fn tokens(node: &SyntaxNode) -> impl DoubleEndedIterator<Item = &Token> {
+    node.children.iter().filter_map(|child| match child {
+        SyntaxElement::Token(token) => Some(token),
+        SyntaxElement::Node(_) => None,
+    })
+}
+
+fn first_token(node: &SyntaxNode) -> Option<&Token> {
+    node.children.iter().find_map(|child| match child {
+        SyntaxElement::Node(node) => first_token(node),
+        SyntaxElement::Token(token) => Some(token),
+    })
+}
+
+fn last_token(node: &SyntaxNode) -> Option<&Token> {
+    node.children.iter().rev().find_map(|child| match child {
+        SyntaxElement::Node(node) => last_token(node),
+        SyntaxElement::Token(token) => Some(token),
+    })
+}
+
+// keeps a document on one line, used inside string interpolations
+fn flatten(doc: Doc) -> Doc {
+    match doc {
+        Doc::Line => text(" "),
+        Doc::SoftLine => text(""),
+        Doc::Group(doc) | Doc::Indent(doc) => flatten(*doc),
+        Doc::Concat(docs) => Doc::Concat(docs.into_iter().map(flatten).collect()),
+        doc => doc,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+struct Printer {
+    output: String,
+    column: usize,
+    // indentation to write before the next text on a fresh line, deferred so
+    // blank lines don't end up with trailing spaces
+    pending_indent: Option<usize>,
+    // pending line comments with the indentation at the point they were seen
+    line_suffix: Vec<(usize, String)>,
+}
+
+impl Printer {
+    fn new() -> Self {
+        Self {
+            output: String::new(),
+            column: 0,
+            pending_indent: None,
+            line_suffix: Vec::new(),
+        }
+    }
+
+    fn print(mut self, doc: &Doc) -> String {
+        let mut stack = vec![(0, Mode::Break, doc)];
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match doc {
+                Doc::Text(s) => {
+                    // nothing may follow a line comment on its line
+                    if !self.line_suffix.is_empty() && !s.is_empty() && !is_punctuation(s) {
+                        self.newline(indent);
+                    }
+                    self.write(s);
+                }
+                Doc::Line if mode == Mode::Flat => self.write(" "),
+                Doc::SoftLine if mode == Mode::Flat => {}
+                Doc::Line | Doc::SoftLine | Doc::HardLine => self.newline(indent),
+                Doc::FreshLine => {
+                    if self.pending_indent.is_none() && !self.output.is_empty() {
+                        self.newline(indent);
+                    }
+                }
+                Doc::LineSuffix(s) => self.line_suffix.push((indent, s.clone())),
+                Doc::Group(inner) => {
+                    let fits = mode == Mode::Flat
+                        || fits(
+                            inner,
+                            &stack,
+                            MAX_WIDTH.saturating_sub(self.column),
+                            !self.line_suffix.is_empty(),
+                        );
+                    let mode = if fits { Mode::Flat } else { Mode::Break };
+                    stack.push((indent, mode, inner));
+                }
+                Doc::Leading(inner) => stack.push((indent, mode, inner)),
+                Doc::Indent(inner) => stack.push((indent + INDENT, mode, inner)),
+                Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|d| (indent, mode, d))),
+            }
+        }
+        self.flush_line_suffix();
+        self.output.push('\n');
+        self.output
+    }
+
+    fn write(&mut self, s: &str) {
+        // spacing meant to follow something else on the line
+        let s = match self.pending_indent {
+            Some(_) => s.trim_start_matches(' '),
+            None => s,
+        };
+        if s.is_empty() {
+            return;
+        }
+        if let Some(indent) = self.pending_indent.take() {
+            self.output.push_str(&" ".repeat(indent));
+        }
+        self.output.push_str(s);
+        self.column = match s.rfind('\n') {
+            Some(i) => s[i + 1..].chars().count(),
+            None => self.column + s.chars().count(),
+        };
+    }
+
+    fn newline(&mut self, indent: usize) {
+        // a space printed before a comment moved to the next line, or before
+        // a line comment bringing its own
+        self.trim_end();
+        self.flush_line_suffix();
+        self.trim_end();
+        self.output.push('\n');
+        self.column = indent;
+        self.pending_indent = Some(indent);
+    }
+
+    fn trim_end(&mut self) {
+        let trimmed = self.output.trim_end_matches(' ').len();
+        self.output.truncate(trimmed);
+    }
+
+    // each line comment ends its line, so any after the first get their own
+    fn flush_line_suffix(&mut self) {
+        for (i, (indent, suffix)) in std::mem::take(&mut self.line_suffix)
+            .into_iter()
+            .enumerate()
+        {
+            if i > 0 {
+                self.output.push('\n');
+                self.pending_indent = Some(indent);
+            }
+            self.write(&suffix);
+        }
+    }
+}
+
+// punctuation ending a line may go before a trailing line comment rather than
+// on a line of its own
+fn is_punctuation(s: &str) -> bool {
+    matches!(s, ";" | "," | ")")
+}
+
+// whether `doc` fits in `width` columns when printed flat, along with what
+// follows it on the same line. `after_line_comment` is whether a line comment
+// is already waiting to end the line
+fn fits(
+    doc: &Doc,
+    rest: &[(usize, Mode, &Doc)],
+    width: usize,
+    mut after_line_comment: bool,
+) -> bool {
+    let mut width = width as isize;
+    let mut rest = rest.iter().rev();
+    let mut stack = vec![(Mode::Flat, doc)];
+    // comments before anything in `doc` are printed ahead of it
+    let mut started = false;
+    loop {
+        let (mode, doc) = match stack.pop() {
+            Some(next) => next,
+            None => match rest.next() {
+                Some((_, mode, doc)) => (*mode, *doc),
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(s) => {
+                if s.contains('\n') {
+                    return false;
+                }
+                if after_line_comment && !s.is_empty() && !is_punctuation(s) {
+                    return false;
+                }
+                width -= s.chars().count() as isize;
+                started |= !s.is_empty();
+            }
+            Doc::Leading(_) if !started => {}
+            Doc::Line if mode == Mode::Flat => {
+                if after_line_comment {
+                    return false;
+                }
+                width -= 1;
+            }
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::HardLine | Doc::FreshLine if mode == Mode::Flat => return false,
+            Doc::Line | Doc::SoftLine | Doc::HardLine | Doc::FreshLine => return true,
+            Doc::LineSuffix(_) => after_line_comment = true,
+            Doc::Group(inner) | Doc::Indent(inner) | Doc::Leading(inner) => {
+                stack.push((mode, inner))
+            }
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|d| (mode, d))),
+        }
+        if width < 0 {
+            return false;
+        }
+    }
+}
@@ -1,42 +1,467 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
 use crate::{
+    class::{LoxClass, LoxInstance},
+    environment::Environment,
     error::LoxError,
-    parser::{BinaryExpr, Expr, GroupingExpr, Literal, UnaryExpr},
-    token::TokenType,
+    function::{Callable, LoxFunction, NativeFunction},
+    parser::{
+        AssignExpr, BinaryExpr, BlockStmt, CallExpr, ClassStmt, CompoundAssignExpr, Expr,
+        ExpressionStmt, GetExpr, GroupingExpr, IfStmt, InterpolationExpr, Literal, LogicalExpr,
+        PrintStmt, ReturnStmt, SetExpr, Stmt, SuperExpr, ThisExpr, UnaryExpr, UpdateExpr, VarStmt,
+        VariableExpr, WhileStmt,
+    },
+    token::{Token, TokenType},
 };
 
-struct Interpreter {}
+pub struct Interpreter {
+    globals: Rc<RefCell<Environment>>,
+    environment: Rc<RefCell<Environment>>,
+    // scope depth of each resolved local, keyed by expression id
+    locals: HashMap<usize, usize>,
+    // number of Lox calls currently on the native stack
+    call_depth: usize,
+}
+
+// deepest call chain allowed before reporting a stack overflow, well within
+// the stack `main` runs the interpreter on
+const MAX_CALL_DEPTH: usize = 10_000;
 
 impl Interpreter {
     pub fn new() -> Self {
-        todo!()
+        let globals = Rc::new(RefCell::new(Environment::new()));
+        globals.borrow_mut().define(
+            "clock".to_string(),
+            RuntimeValue::NativeFunction(Rc::new(NativeFunction {
+                name: "clock",
+                arity: 0,
+                function: |_| {
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default();
+                    RuntimeValue::Number(now.as_secs_f64())
+                },
+            })),
+        );
+
+        Interpreter {
+            environment: Rc::clone(&globals),
+            globals,
+            locals: HashMap::new(),
+            call_depth: 0,
+        }
+    }
+
+    pub fn resolve(&mut self, id: usize, depth: usize) {
+        self.locals.insert(id, depth);
     }
 
-    fn evaluate(&self, expr: Expr) -> Result<RuntimeValue, LoxError> {
+    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<(), LoxError> {
+        for statement in statements {
+            self.execute(statement)?;
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, stmt: &Stmt) -> Result<(), LoxError> {
+        match stmt {
+            Stmt::Block(b) => {
+                let BlockStmt { statements, .. } = b;
+                let environment = Environment::new_enclosing(Rc::clone(&self.environment));
+                self.execute_block(statements, Rc::new(RefCell::new(environment)))?;
+            }
+            Stmt::Class(c) => {
+                let ClassStmt {
+                    name,
+                    superclass,
+                    methods,
+                    ..
+                } = c;
+
+                let superclass = match superclass {
+                    Some(superclass) => {
+                        match self.look_up_variable(&superclass.name, superclass.id)? {
+                            RuntimeValue::Class(class) => Some(class),
+                            _ => {
+                                return Err(LoxError::runtime(
+                                    &superclass.name,
+                                    "Superclass must be a class.",
+                                ))
+                            }
+                        }
+                    }
+                    None => None,
+                };
+
+                self.environment
+                    .borrow_mut()
+                    .define(name.lexeme.to_string(), RuntimeValue::Nil);
+
+                // methods of a subclass close over an extra scope holding `super`
+                let enclosing = Rc::clone(&self.environment);
+                if let Some(superclass) = &superclass {
+                    let mut environment = Environment::new_enclosing(Rc::clone(&enclosing));
+                    environment.define(
+                        "super".to_string(),
+                        RuntimeValue::Class(Rc::clone(superclass)),
+                    );
+                    self.environment = Rc::new(RefCell::new(environment));
+                }
+
+                let mut class_methods = HashMap::new();
+                for method in methods {
+                    let function = LoxFunction::new(
+                        method.clone(),
+                        Rc::clone(&self.environment),
+                        &*method.name.lexeme == "init",
+                    );
+                    class_methods.insert(method.name.lexeme.to_string(), Rc::new(function));
+                }
+
+                let class = LoxClass::new(name.lexeme.to_string(), superclass, class_methods);
+                self.environment = enclosing;
+                self.environment
+                    .borrow_mut()
+                    .assign(name, RuntimeValue::Class(Rc::new(class)))?;
+            }
+            Stmt::Expression(e) => {
+                let ExpressionStmt { expression, .. } = e;
+                self.evaluate(expression)?;
+            }
+            Stmt::Function(f) => {
+                let function = LoxFunction::new(f.clone(), Rc::clone(&self.environment), false);
+                self.environment.borrow_mut().define(
+                    f.name.lexeme.to_string(),
+                    RuntimeValue::Function(Rc::new(function)),
+                );
+            }
+            Stmt::If(i) => {
+                let IfStmt {
+                    condition,
+                    then_branch,
+                    else_branch,
+                    ..
+                } = i;
+                if self.evaluate(condition)?.is_truthy() {
+                    self.execute(then_branch)?;
+                } else if let Some(else_branch) = else_branch {
+                    self.execute(else_branch)?;
+                }
+            }
+            Stmt::Print(p) => {
+                let PrintStmt { expression, .. } = p;
+                let value = self.evaluate(expression)?;
+                println!("{}", value);
+            }
+            Stmt::Return(r) => {
+                let ReturnStmt { value, .. } = r;
+                let value = match value {
+                    Some(value) => self.evaluate(value)?,
+                    None => RuntimeValue::Nil,
+                };
+                return Err(LoxError::Return(value));
+            }
+            Stmt::Var(v) => {
+                let VarStmt {
+                    name, initializer, ..
+                } = v;
+                let value = match initializer {
+                    Some(initializer) => self.evaluate(initializer)?,
+                    None => RuntimeValue::Nil,
+                };
+                self.environment
+                    .borrow_mut()
+                    .define(name.lexeme.to_string(), value);
+            }
+            Stmt::While(w) => {
+                let WhileStmt {
+                    condition, body, ..
+                } = w;
+                while self.evaluate(condition)?.is_truthy() {
+                    self.execute(body)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn execute_block(
+        &mut self,
+        statements: &[Stmt],
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<(), LoxError> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+
+        let result = statements
+            .iter()
+            .try_for_each(|statement| self.execute(statement));
+
+        self.environment = previous;
+        result
+    }
+
+    fn look_up_variable(&self, name: &Token, id: usize) -> Result<RuntimeValue, LoxError> {
+        match self.locals.get(&id) {
+            Some(distance) => Ok(Environment::get_at(
+                &self.environment,
+                *distance,
+                &name.lexeme,
+            )),
+            None => self.globals.borrow().get(name),
+        }
+    }
+
+    fn assign_variable(
+        &mut self,
+        name: &Token,
+        id: usize,
+        value: RuntimeValue,
+    ) -> Result<(), LoxError> {
+        match self.locals.get(&id) {
+            Some(distance) => Environment::assign_at(&self.environment, *distance, name, value),
+            None => self.globals.borrow_mut().assign(name, value)?,
+        }
+        Ok(())
+    }
+
+    // applies the arithmetic behind `++`, `--` or a compound assignment to the
+    // target, reading it and evaluating the receiver of a property only once.
+    // yields the values before and after the update
+    fn update(
+        &mut self,
+        target: &Expr,
+        operator: &Token,
+        value: Option<&Expr>,
+    ) -> Result<(RuntimeValue, RuntimeValue), LoxError> {
+        let arithmetic = match operator.token_type {
+            TokenType::PlusEqual | TokenType::PlusPlus => TokenType::Plus,
+            TokenType::MinusEqual | TokenType::MinusMinus => TokenType::Minus,
+            TokenType::StarEqual => TokenType::Star,
+            _ => TokenType::Slash,
+        };
+        match target {
+            Expr::Variable(VariableExpr { id, name, .. }) => {
+                let old = self.look_up_variable(name, *id)?;
+                let right = self.update_operand(value)?;
+                let new = Self::binary(arithmetic, operator, old.clone(), right)?;
+                self.assign_variable(name, *id, new.clone())?;
+                Ok((old, new))
+            }
+            Expr::Get(GetExpr { object, name, .. }) => {
+                let instance = match self.evaluate(object)? {
+                    RuntimeValue::Instance(instance) => instance,
+                    _ => return Err(LoxError::runtime(name, "Only instances have fields.")),
+                };
+                let old = LoxInstance::get(&instance, name)?;
+                let right = self.update_operand(value)?;
+                let new = Self::binary(arithmetic, operator, old.clone(), right)?;
+                instance.borrow_mut().set(name, new.clone());
+                Ok((old, new))
+            }
+            _ => Err(LoxError::runtime(operator, "Invalid assignment target.")),
+        }
+    }
+
+    // the right-hand side of a compound assignment, or 1 for `++` and `--`
+    fn update_operand(&mut self, value: Option<&Expr>) -> Result<RuntimeValue, LoxError> {
+        match value {
+            Some(value) => self.evaluate(value),
+            None => Ok(RuntimeValue::Number(1.0)),
+        }
+    }
+
+    fn evaluate(&mut self, expr: &Expr) -> Result<RuntimeValue, LoxError> {
         match expr {
-            Expr::Literal(l) => match l {
-                Literal::Number(n) => Ok(RuntimeValue::Number(n)),
+            Expr::Literal(l) => match &l.value {
+                Literal::Number(n) => Ok(RuntimeValue::Number(*n)),
                 Literal::String(s) => Ok(RuntimeValue::String(s.clone())),
-                Literal::Boolean(b) => Ok(RuntimeValue::Boolean(b)),
+                Literal::Boolean(b) => Ok(RuntimeValue::Boolean(*b)),
                 Literal::Nil => Ok(RuntimeValue::Nil),
             },
+            Expr::Variable(v) => {
+                let VariableExpr { id, name, .. } = v;
+                self.look_up_variable(name, *id)
+            }
+            Expr::Assign(a) => {
+                let AssignExpr {
+                    id, name, value, ..
+                } = a;
+                let value = self.evaluate(value)?;
+                self.assign_variable(name, *id, value.clone())?;
+                Ok(value)
+            }
+            Expr::Logical(l) => {
+                let LogicalExpr {
+                    left,
+                    operator,
+                    right,
+                    ..
+                } = l;
+                let left = self.evaluate(left)?;
+
+                // short-circuit, yielding the operand itself rather than a bool
+                if operator.token_type == TokenType::Or {
+                    if left.is_truthy() {
+                        return Ok(left);
+                    }
+                } else if !left.is_truthy() {
+                    return Ok(left);
+                }
+
+                self.evaluate(right)
+            }
+            Expr::Call(c) => {
+                let CallExpr {
+                    callee,
+                    paren,
+                    arguments,
+                    ..
+                } = c;
+                let callee = self.evaluate(callee)?;
+
+                let mut values = Vec::new();
+                for argument in arguments {
+                    values.push(self.evaluate(argument)?);
+                }
+
+                let function: &dyn Callable = match &callee {
+                    RuntimeValue::Function(f) => f.as_ref(),
+                    RuntimeValue::NativeFunction(f) => f.as_ref(),
+                    RuntimeValue::Class(c) => c,
+                    _ => {
+                        return Err(LoxError::runtime(
+                            paren,
+                            "Can only call functions and classes.",
+                        ))
+                    }
+                };
+
+                if values.len() != function.arity() {
+                    return Err(LoxError::runtime(
+                        paren,
+                        &format!(
+                            "Expected {} arguments but got {}.",
+                            function.arity(),
+                            values.len()
+                        ),
+                    ));
+                }
+
+                if self.call_depth == MAX_CALL_DEPTH {
+                    return Err(LoxError::runtime(paren, "Stack overflow."));
+                }
+                self.call_depth += 1;
+                let result = function.call(self, values);
+                self.call_depth -= 1;
+                result
+            }
+            Expr::CompoundAssign(c) => {
+                let CompoundAssignExpr {
+                    target,
+                    operator,
+                    value,
+                    ..
+                } = c;
+                let (_, new) = self.update(target, operator, Some(value))?;
+                Ok(new)
+            }
+            Expr::Update(u) => {
+                let UpdateExpr {
+                    target,
+                    operator,
+                    prefix,
+                    ..
+                } = u;
+                let (old, new) = self.update(target, operator, None)?;
+                Ok(if *prefix { new } else { old })
+            }
+            Expr::Get(g) => {
+                let GetExpr { object, name, .. } = g;
+                match self.evaluate(object)? {
+                    RuntimeValue::Instance(instance) => LoxInstance::get(&instance, name),
+                    _ => Err(LoxError::runtime(name, "Only instances have properties.")),
+                }
+            }
+            Expr::Set(s) => {
+                let SetExpr {
+                    object,
+                    name,
+                    value,
+                    ..
+                } = s;
+                match self.evaluate(object)? {
+                    RuntimeValue::Instance(instance) => {
+                        let value = self.evaluate(value)?;
+                        instance.borrow_mut().set(name, value.clone());
+                        Ok(value)
+                    }
+                    _ => Err(LoxError::runtime(name, "Only instances have fields.")),
+                }
+            }
+            Expr::Super(s) => {
+                let SuperExpr {
+                    id,
+                    keyword,
+                    method,
+                    ..
+                } = s;
+                // `this` is always bound one scope inside the one holding `super`
+                let distance = self.locals.get(id).copied().unwrap_or_default();
+                let superclass = Environment::get_at(&self.environment, distance, "super");
+                let object =
+                    Environment::get_at(&self.environment, distance.saturating_sub(1), "this");
+
+                match (superclass, object) {
+                    (RuntimeValue::Class(superclass), RuntimeValue::Instance(instance)) => {
+                        match superclass.find_method(&method.lexeme) {
+                            Some(method) => {
+                                Ok(RuntimeValue::Function(Rc::new(method.bind(instance))))
+                            }
+                            None => Err(LoxError::runtime(
+                                method,
+                                &format!("Undefined property '{}'.", method.lexeme),
+                            )),
+                        }
+                    }
+                    _ => Err(LoxError::runtime(keyword, "Can't use 'super' here.")),
+                }
+            }
+            Expr::This(t) => {
+                let ThisExpr { id, keyword, .. } = t;
+                self.look_up_variable(keyword, *id)
+            }
             Expr::Grouping(g) => {
-                let GroupingExpr { expression } = g;
-                self.evaluate(*expression)
+                let GroupingExpr { expression, .. } = g;
+                self.evaluate(expression)
+            }
+            Expr::Interpolation(i) => {
+                let InterpolationExpr { parts, .. } = i;
+                let mut value = String::new();
+                for part in parts {
+                    value += &self.evaluate(part)?.to_string();
+                }
+                Ok(RuntimeValue::String(value))
             }
             Expr::Unary(u) => {
-                let UnaryExpr { operator, right } = u;
-                let right = self.evaluate(*right)?;
+                let UnaryExpr {
+                    operator, right, ..
+                } = u;
+                let right = self.evaluate(right)?;
                 match operator.token_type {
                     TokenType::Minus => {
                         if let RuntimeValue::Number(r) = right {
                             return Ok(RuntimeValue::Number(-r));
                         }
-                        return Err(LoxError::RuntimeError());
+                        Err(LoxError::runtime(operator, "Operand must be a number."))
                     }
-                    TokenType::Bang => {
-                        return Ok(RuntimeValue::Boolean(!right.is_truthy()));
-                    }
-                    _ => Err(LoxError::RuntimeError()),
+                    TokenType::Bang => Ok(RuntimeValue::Boolean(!right.is_truthy())),
+                    _ => Err(LoxError::runtime(operator, "Unknown unary operator.")),
                 }
             }
             Expr::Binary(b) => {
@@ -44,86 +469,117 @@ impl Interpreter {
                     left,
                     operator,
                     right,
+                    ..
                 } = b;
-                let left = self.evaluate(*left)?;
-                let right = self.evaluate(*right)?;
-                match operator.token_type {
-                    TokenType::Minus => match (left, right) {
-                        (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
-                            Ok(RuntimeValue::Number(l - r))
-                        }
-                        _ => Err(LoxError::RuntimeError()),
-                    },
-                    TokenType::Slash => match (left, right) {
-                        (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
-                            Ok(RuntimeValue::Number(l / r))
-                        }
-                        _ => Err(LoxError::RuntimeError()),
-                    },
-                    TokenType::Star => match (left, right) {
-                        (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
-                            Ok(RuntimeValue::Number(l * r))
-                        }
-                        _ => Err(LoxError::RuntimeError()),
-                    },
-                    TokenType::Plus => match (left, right) {
-                        (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
-                            Ok(RuntimeValue::Number(l + r))
-                        }
-                        (RuntimeValue::String(l), RuntimeValue::String(r)) => {
-                            Ok(RuntimeValue::String(format!("{}{}", l, r)))
-                        }
-                        _ => Err(LoxError::RuntimeError()),
-                    },
-                    TokenType::Greater => match (left, right) {
-                        (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
-                            Ok(RuntimeValue::Boolean(l > r))
-                        }
-                        _ => Err(LoxError::RuntimeError()),
-                    },
-                    TokenType::GreaterEqual => match (left, right) {
-                        (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
-                            Ok(RuntimeValue::Boolean(l >= r))
-                        }
-                        _ => Err(LoxError::RuntimeError()),
-                    },
-                    TokenType::Less => match (left, right) {
-                        (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
-                            Ok(RuntimeValue::Boolean(l < r))
-                        }
-                        _ => Err(LoxError::RuntimeError()),
-                    },
-                    TokenType::LessEqual => match (left, right) {
-                        (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
-                            Ok(RuntimeValue::Boolean(l <= r))
-                        }
-                        _ => Err(LoxError::RuntimeError()),
-                    },
-                    TokenType::BangEqual => Ok(RuntimeValue::Boolean(!left.is_equal(right))),
-                    TokenType::EqualEqual => Ok(RuntimeValue::Boolean(left.is_equal(right))),
-                    _ => Err(LoxError::RuntimeError()),
-                }
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                Self::binary(operator.token_type, operator, left, right)
             }
-            _ => Err(LoxError::RuntimeError()),
         }
     }
+
+    // `operator` is where errors are reported, and differs from the operation
+    // for compound assignments
+    fn binary(
+        token_type: TokenType,
+        operator: &Token,
+        left: RuntimeValue,
+        right: RuntimeValue,
+    ) -> Result<RuntimeValue, LoxError> {
+        match token_type {
+            TokenType::Minus => match (left, right) {
+                (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
+                    Ok(RuntimeValue::Number(l - r))
+                }
+                _ => Err(LoxError::runtime(operator, "Operands must be numbers.")),
+            },
+            TokenType::Slash => match (left, right) {
+                (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
+                    Ok(RuntimeValue::Number(l / r))
+                }
+                _ => Err(LoxError::runtime(operator, "Operands must be numbers.")),
+            },
+            TokenType::Percent => match (left, right) {
+                (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
+                    Ok(RuntimeValue::Number(l % r))
+                }
+                _ => Err(LoxError::runtime(operator, "Operands must be numbers.")),
+            },
+            TokenType::Star => match (left, right) {
+                (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
+                    Ok(RuntimeValue::Number(l * r))
+                }
+                _ => Err(LoxError::runtime(operator, "Operands must be numbers.")),
+            },
+            TokenType::Plus => match (left, right) {
+                (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
+                    Ok(RuntimeValue::Number(l + r))
+                }
+                (RuntimeValue::String(l), RuntimeValue::String(r)) => {
+                    Ok(RuntimeValue::String(format!("{}{}", l, r)))
+                }
+                _ => Err(LoxError::runtime(
+                    operator,
+                    "Operands must be two numbers or two strings.",
+                )),
+            },
+            TokenType::Greater => match (left, right) {
+                (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
+                    Ok(RuntimeValue::Boolean(l > r))
+                }
+                _ => Err(LoxError::runtime(operator, "Operands must be numbers.")),
+            },
+            TokenType::GreaterEqual => match (left, right) {
+                (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
+                    Ok(RuntimeValue::Boolean(l >= r))
+                }
+                _ => Err(LoxError::runtime(operator, "Operands must be numbers.")),
+            },
+            TokenType::Less => match (left, right) {
+                (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
+                    Ok(RuntimeValue::Boolean(l < r))
+                }
+                _ => Err(LoxError::runtime(operator, "Operands must be numbers.")),
+            },
+            TokenType::LessEqual => match (left, right) {
+                (RuntimeValue::Number(l), RuntimeValue::Number(r)) => {
+                    Ok(RuntimeValue::Boolean(l <= r))
+                }
+                _ => Err(LoxError::runtime(operator, "Operands must be numbers.")),
+            },
+            TokenType::BangEqual => Ok(RuntimeValue::Boolean(!left.is_equal(right))),
+            TokenType::EqualEqual => Ok(RuntimeValue::Boolean(left.is_equal(right))),
+            _ => Err(LoxError::runtime(operator, "Unknown binary operator.")),
+        }
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
+#[derive(Debug, Clone)]
 pub enum RuntimeValue {
     String(String),
     Number(f64),
     Boolean(bool),
     Nil,
+    Function(Rc<LoxFunction>),
+    NativeFunction(Rc<NativeFunction>),
+    Class(Rc<LoxClass>),
+    Instance(Rc<RefCell<LoxInstance>>),
 }
 
 impl RuntimeValue {
     pub fn is_truthy(&self) -> bool {
         if let RuntimeValue::Nil = self {
-            return false;
+            false
         } else if let RuntimeValue::Boolean(b) = self {
-            return *b;
+            *b
         } else {
-            return true;
+            true
         }
     }
 
@@ -133,7 +589,26 @@ impl RuntimeValue {
             (RuntimeValue::String(s), RuntimeValue::String(r)) => *s == r,
             (RuntimeValue::Number(s), RuntimeValue::Number(r)) => *s == r,
             (RuntimeValue::Boolean(s), RuntimeValue::Boolean(r)) => *s == r,
+            (RuntimeValue::Function(s), RuntimeValue::Function(r)) => Rc::ptr_eq(s, &r),
+            (RuntimeValue::NativeFunction(s), RuntimeValue::NativeFunction(r)) => Rc::ptr_eq(s, &r),
+            (RuntimeValue::Class(s), RuntimeValue::Class(r)) => Rc::ptr_eq(s, &r),
+            (RuntimeValue::Instance(s), RuntimeValue::Instance(r)) => Rc::ptr_eq(s, &r),
             (_, _) => false,
         }
     }
 }
+
+impl fmt::Display for RuntimeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeValue::String(s) => write!(f, "{}", s),
+            RuntimeValue::Number(n) => write!(f, "{}", n),
+            RuntimeValue::Boolean(b) => write!(f, "{}", b),
+            RuntimeValue::Nil => write!(f, "nil"),
+            RuntimeValue::Function(function) => write!(f, "{}", function),
+            RuntimeValue::NativeFunction(function) => write!(f, "{}", function),
+            RuntimeValue::Class(class) => write!(f, "{}", class),
+            RuntimeValue::Instance(instance) => write!(f, "{}", instance.borrow()),
+        }
+    }
+}
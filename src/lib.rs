@@ -1,5 +1,14 @@
+pub mod ast_printer;
+pub mod class;
+pub mod cst;
+pub mod diagnostic;
+pub mod environment;
 pub mod error;
+pub mod formatter;
+pub mod function;
 pub mod interpreter;
 pub mod parser;
+pub mod resolver;
 pub mod scanner;
 pub mod token;
+pub mod visitor;
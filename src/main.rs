@@ -1,72 +1,196 @@
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::process;
-use std::{io, io::BufRead, io::Read};
+use std::thread;
+use std::{io, io::BufRead, io::IsTerminal, io::Read, io::Write};
 
+use lox_rs::ast_printer::{AstFormat, AstPrinter};
+use lox_rs::diagnostic::DiagnosticRenderer;
 use lox_rs::error::LoxError;
+use lox_rs::formatter;
+use lox_rs::interpreter::Interpreter;
 use lox_rs::parser::Parser;
+use lox_rs::resolver::Resolver;
 use lox_rs::scanner::Scanner;
-use lox_rs::token::TokenType;
 
-// map error to cmd line error
-fn run_file(file_path: &str) -> Result<(), LoxError> {
-    let mut f = File::open(&file_path)?;
+#[derive(Clone, Copy, PartialEq)]
+enum ErrorFormat {
+    Human,
+    Json,
+}
+
+fn report(renderer: &DiagnosticRenderer, format: ErrorFormat, errors: &[LoxError]) -> String {
+    errors
+        .iter()
+        .map(|e| match format {
+            ErrorFormat::Human => renderer.render(e),
+            ErrorFormat::Json => renderer.render_json(e),
+        })
+        .collect()
+}
+
+// reports any errors and maps them to the process exit code
+fn run_file(file_path: &str, format: ErrorFormat, dump: Option<AstFormat>) -> Result<(), i32> {
     let mut source = String::new();
+    let result = match File::open(file_path).and_then(|mut f| f.read_to_string(&mut source)) {
+        Ok(_) => run(&mut Interpreter::new(), Scanner::new(&source), dump),
+        Err(e) => Err(vec![e.into()]),
+    };
 
-    f.read_to_string(&mut source)?;
+    result.map_err(|errors| {
+        let renderer = DiagnosticRenderer::new(file_path, &source, io::stderr().is_terminal());
+        eprint!("{}", report(&renderer, format, &errors));
+        exit_code(&errors)
+    })
+}
+
+// formats the files in place, or with `check` only reports the ones that
+// aren't formatted
+fn run_fmt(paths: &[String], check: bool, format: ErrorFormat) -> Result<(), i32> {
+    let mut result = Ok(());
+    for path in paths {
+        let mut source = String::new();
+        let formatted = match File::open(path).and_then(|mut f| f.read_to_string(&mut source)) {
+            Ok(_) => formatter::format(&source),
+            Err(e) => Err(vec![e.into()]),
+        };
+        let written = match formatted {
+            Ok(formatted) if formatted == source => Ok(()),
+            Ok(_) if check => {
+                match format {
+                    ErrorFormat::Human => eprintln!("Would reformat {}", path),
+                    ErrorFormat::Json => {
+                        let renderer = DiagnosticRenderer::new(path, &source, false);
+                        eprint!("{}", renderer.render_json_note("Would reformat."));
+                    }
+                }
+                result = result.and(Err(1));
+                Ok(())
+            }
+            Ok(formatted) => fs::write(path, formatted).map_err(|e| vec![e.into()]),
+            Err(errors) => Err(errors),
+        };
 
-    Ok(run(&source)?)
+        if let Err(errors) = written {
+            let renderer = DiagnosticRenderer::new(path, &source, io::stderr().is_terminal());
+            eprint!("{}", report(&renderer, format, &errors));
+            // errors take precedence over unformatted files
+            result = Err(exit_code(&errors));
+        }
+    }
+    result
 }
 
-fn run_prompt() {
+fn run_prompt(format: ErrorFormat, dump: Option<AstFormat>) {
     let stdin = io::stdin();
     let mut handler = stdin.lock();
+    let mut interpreter = Interpreter::new();
+    // everything entered so far, as functions declared on earlier lines can
+    // report errors pointing back at them
+    let mut history = String::new();
 
-    loop {
+    for line_number in 1.. {
         print!("> ");
-        let mut line = String::new();
-        if handler.read_line(&mut line).is_err() || line.is_empty() {
+        let _ = io::stdout().flush();
+        let start = history.len();
+        if handler.read_line(&mut history).is_err() || history.len() == start {
             return;
         }
 
-        match run(&line) {
-            Ok(_) => (),
-            Err(e) => println!("{}", e),
-        };
+        let scanner = Scanner::starting_at(&history, start, line_number);
+        if let Err(errors) = run(&mut interpreter, scanner, dump) {
+            let renderer = DiagnosticRenderer::new("<stdin>", &history, io::stdout().is_terminal());
+            print!("{}", report(&renderer, format, &errors));
+        }
     }
 }
 
-fn run(source: &str) -> Result<(), LoxError> {
-    let mut scanner = Scanner::new(source);
-    let tokens = scanner.scan_tokens();
-    for token in &tokens {
-        println!("{:?}", token);
+// with `dump` the syntax tree is printed instead of run
+fn run(
+    interpreter: &mut Interpreter,
+    mut scanner: Scanner,
+    dump: Option<AstFormat>,
+) -> Result<(), Vec<LoxError>> {
+    let parsed = Parser::new(&mut scanner).parse();
+    let mut errors = scanner.take_errors();
+
+    // syntax errors are reported alongside lexical ones, but a program with
+    // lexical errors is never run
+    let statements = match parsed {
+        Ok(statements) if errors.is_empty() => statements,
+        Ok(_) => return Err(errors),
+        Err(parse_errors) => {
+            errors.extend(parse_errors);
+            return Err(errors);
+        }
+    };
+
+    if let Some(dump) = dump {
+        print!("{}", AstPrinter::new(dump).print(&statements));
+        return Ok(());
     }
-    let mut parser = Parser::new(tokens);
 
-    let expr = parser.parse()?;
+    Resolver::new(interpreter).resolve(&statements)?;
 
-    println!("Expr: {:?}", expr);
-    Ok(())
+    interpreter.interpret(&statements).map_err(|e| vec![e])
 }
 
+// exit codes follow the reference jlox/clox implementations (sysexits.h)
+fn exit_code(errors: &[LoxError]) -> i32 {
+    match errors.first() {
+        Some(LoxError::RuntimeError(..)) | Some(LoxError::Return(_)) => 70,
+        Some(LoxError::Io(_)) => 74,
+        _ => 65,
+    }
+}
+
+// every Lox call recurses through the interpreter, so it runs on a thread
+// with room for the deepest call chain it allows
+const STACK_SIZE: usize = 1 << 30;
+
 fn main() {
-    let args: Vec<String> = env::args().into_iter().skip(1).collect();
+    let cli = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(cli)
+        .expect("failed to spawn the interpreter thread");
+    process::exit(cli.join().unwrap_or(101));
+}
 
-    if args.len() > 1 {
-        eprintln!("Usage: lox-rs [script]");
-        process::exit(1);
-    } else if args.len() == 1 {
-        match run_file(&args[0]) {
-            Ok(_) => (),
-            Err(e) => {
-                eprintln!("error running file: {}", e);
-                process::exit(1);
-            }
+fn cli() -> i32 {
+    let mut format = ErrorFormat::Human;
+    let mut check = false;
+    let mut dump = None;
+    let mut args = Vec::new();
+    let mut usage_error = false;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--error-format=human" => format = ErrorFormat::Human,
+            "--error-format=json" => format = ErrorFormat::Json,
+            "--check" => check = true,
+            "--dump-ast=sexpr" => dump = Some(AstFormat::SExpr),
+            "--dump-ast=tree" => dump = Some(AstFormat::Tree),
+            _ if arg.starts_with("--") => usage_error = true,
+            _ => args.push(arg),
         }
-    } else {
-        run_prompt();
     }
 
-    process::exit(0);
+    let fmt = args.first().is_some_and(|arg| arg == "fmt");
+    let usage_error = usage_error
+        || (fmt && (args.len() == 1 || dump.is_some()))
+        || (!fmt && (args.len() > 1 || check));
+    if usage_error {
+        eprintln!("Usage: lox-rs [--error-format=human|json] [--dump-ast=sexpr|tree] [script]");
+        eprintln!("       lox-rs fmt [--check] [--error-format=human|json] <file>...");
+        return 64;
+    }
+
+    let result = if fmt {
+        run_fmt(&args[1..], check, format)
+    } else if args.len() == 1 {
+        run_file(&args[0], format, dump)
+    } else {
+        run_prompt(format, dump);
+        Ok(())
+    };
+    result.err().unwrap_or(0)
 }
@@ -1,46 +1,437 @@
+use std::{
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
 use crate::{
     error::LoxError,
-    token::{self, Token, TokenType},
+    token::{self, Span, Token, TokenType},
 };
 
 /*
 
-Complete Expression Grammar
+Complete Grammar
+
+program        → declaration* EOF ;
+
+declaration    → classDecl
+               | funDecl
+               | varDecl
+               | statement ;
+
+classDecl      → "class" IDENTIFIER ( "<" IDENTIFIER )?
+                 "{" function* "}" ;
+funDecl        → "fun" function ;
+function       → IDENTIFIER "(" parameters? ")" block ;
+parameters     → IDENTIFIER ( "," IDENTIFIER )* ;
 
-expression     → equality ;
+varDecl        → "var" IDENTIFIER ( "=" expression )? ";" ;
+
+statement      → exprStmt
+               | forStmt
+               | ifStmt
+               | printStmt
+               | returnStmt
+               | whileStmt
+               | block ;
+
+exprStmt       → expression ";" ;
+forStmt        → "for" "(" ( varDecl | exprStmt | ";" )
+                 expression? ";"
+                 expression? ")" statement ;
+ifStmt         → "if" "(" expression ")" statement
+                 ( "else" statement )? ;
+printStmt      → "print" expression ";" ;
+returnStmt     → "return" expression? ";" ;
+whileStmt      → "while" "(" expression ")" statement ;
+block          → "{" declaration* "}" ;
+
+expression     → assignment ;
+assignment     → ( call "." )? IDENTIFIER
+                 ( "=" | "+=" | "-=" | "*=" | "/=" ) assignment
+               | logic_or ;
+logic_or       → logic_and ( "or" logic_and )* ;
+logic_and      → equality ( "and" equality )* ;
 equality       → comparison ( ( "!=" | "==" ) comparison )* ;
 comparison     → term ( ( ">" | ">=" | "<" | "<=" ) term )* ;
 term           → factor ( ( "-" | "+" ) factor )* ;
-factor         → unary ( ( "/" | "*" ) unary )* ;
+factor         → unary ( ( "/" | "*" | "%" ) unary )* ;
 unary          → ( "!" | "-" ) unary
-               | primary ;
-primary        → NUMBER | STRING | "true" | "false" | "nil"
-               | "(" expression ")" ;
+               | ( "++" | "--" ) unary
+               | postfix ;
+postfix        → call ( "++" | "--" )? ;
+call           → primary ( "(" arguments? ")" | "." IDENTIFIER )* ;
+arguments      → expression ( "," expression )* ;
+primary        → NUMBER | STRING | "true" | "false" | "nil" | "this"
+               | "(" expression ")" | IDENTIFIER
+               | "super" "." IDENTIFIER | interpolation ;
+interpolation  → ( INTERPOLATION expression )+ STRING ;
 
 */
 
-pub struct Parser {
-    tokens: Vec<Token>,
-    curr: usize,
+const MAX_ARGUMENTS: usize = 255;
+
+// ids are unique across parsers so that resolutions recorded by the
+// interpreter stay valid for closures defined in earlier REPL lines
+static NEXT_EXPR_ID: AtomicUsize = AtomicUsize::new(0);
+
+fn next_expr_id() -> usize {
+    NEXT_EXPR_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Recursive descent parser pulling tokens on demand from any token source,
+/// such as a `Scanner`. The source is expected to end with an `Eof` token.
+pub struct Parser<I: Iterator<Item = Token>> {
+    tokens: I,
+    // one token lookahead: `current` is the next token to be consumed and
+    // `previous` the one just consumed
+    current: Token,
+    previous: Token,
+    errors: Vec<LoxError>,
 }
 
-impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, curr: 0 }
+impl<I: Iterator<Item = Token>> Parser<I> {
+    pub fn new<T>(tokens: T) -> Self
+    where
+        T: IntoIterator<IntoIter = I>,
+    {
+        let mut tokens = tokens.into_iter();
+        let current = tokens
+            .next()
+            .unwrap_or_else(|| Token::new(TokenType::Eof, "".into(), None, Span::default()));
+        Parser {
+            tokens,
+            // never read before the first token is consumed
+            previous: current.clone(),
+            current,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Parses the whole program, recovering at statement boundaries so that
+    /// every syntax error in the source is reported rather than just the first.
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, Vec<LoxError>> {
+        let mut statements = Vec::new();
+        while !self.is_at_end() {
+            if let Some(statement) = self.declaration() {
+                statements.push(statement);
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    fn declaration(&mut self) -> Option<Stmt> {
+        match self.try_declaration() {
+            Ok(statement) => Some(statement),
+            Err(e) => {
+                self.errors.push(e);
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    fn try_declaration(&mut self) -> Result<Stmt, LoxError> {
+        if self.match_token(&[TokenType::Class]) {
+            return self.class_declaration();
+        }
+        if self.match_token(&[TokenType::Fun]) {
+            let start = self.previous().span;
+            return Ok(Stmt::Function(self.function("function", start)?));
+        }
+        if self.match_token(&[TokenType::Var]) {
+            return self.var_declaration();
+        }
+
+        self.statement()
+    }
+
+    fn class_declaration(&mut self) -> Result<Stmt, LoxError> {
+        let start = self.previous().span;
+        let name = self
+            .consume(TokenType::Identifer, "Expect class name.")?
+            .clone();
+
+        let mut superclass = None;
+        if self.match_token(&[TokenType::Less]) {
+            let name = self
+                .consume(TokenType::Identifer, "Expect superclass name.")?
+                .clone();
+            superclass = Some(VariableExpr {
+                id: next_expr_id(),
+                span: name.span,
+                name,
+            });
+        }
+
+        self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
+
+        let mut methods = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            let start = self.peek().span;
+            methods.push(self.function("method", start)?);
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after class body.")?;
+
+        Ok(Stmt::Class(ClassStmt {
+            name,
+            superclass,
+            methods,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn function(&mut self, kind: &str, start: Span) -> Result<FunctionStmt, LoxError> {
+        let name = self
+            .consume(TokenType::Identifer, &format!("Expect {} name.", kind))?
+            .clone();
+
+        self.consume(
+            TokenType::LeftParens,
+            &format!("Expect '(' after {} name.", kind),
+        )?;
+        let mut params = Vec::new();
+        if !self.check(&TokenType::RightParens) {
+            loop {
+                if params.len() >= MAX_ARGUMENTS {
+                    return Err(LoxError::ParserError(
+                        format!("Can't have more than {} parameters.", MAX_ARGUMENTS),
+                        self.peek().clone(),
+                    ));
+                }
+
+                params.push(
+                    self.consume(TokenType::Identifer, "Expect parameter name.")?
+                        .clone(),
+                );
+
+                if !self.match_token(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightParens, "Expect ')' after parameters.")?;
+
+        self.consume(
+            TokenType::LeftBrace,
+            &format!("Expect '{{' before {} body.", kind),
+        )?;
+        let body = Rc::new(self.block()?);
+
+        Ok(FunctionStmt {
+            name,
+            params,
+            body,
+            span: self.span_from(start),
+        })
+    }
+
+    fn var_declaration(&mut self) -> Result<Stmt, LoxError> {
+        let start = self.previous().span;
+        let name = self
+            .consume(TokenType::Identifer, "Expect variable name.")?
+            .clone();
+
+        let mut initializer = None;
+        if self.match_token(&[TokenType::Equal]) {
+            initializer = Some(self.expression()?);
+        }
+
+        self.consume(
+            TokenType::SemiColon,
+            "Expect ';' after variable declaration.",
+        )?;
+        Ok(Stmt::Var(VarStmt {
+            name,
+            initializer,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn statement(&mut self) -> Result<Stmt, LoxError> {
+        if self.match_token(&[TokenType::For]) {
+            return self.for_statement();
+        }
+        if self.match_token(&[TokenType::If]) {
+            return self.if_statement();
+        }
+        if self.match_token(&[TokenType::Print]) {
+            return self.print_statement();
+        }
+        if self.match_token(&[TokenType::Return]) {
+            return self.return_statement();
+        }
+        if self.match_token(&[TokenType::While]) {
+            return self.while_statement();
+        }
+        if self.match_token(&[TokenType::LeftBrace]) {
+            let start = self.previous().span;
+            let statements = self.block()?;
+            return Ok(Stmt::Block(BlockStmt {
+                statements,
+                span: self.span_from(start),
+            }));
+        }
+
+        self.expression_statement()
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, LoxError> {
+        let mut statements = Vec::new();
+
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            if let Some(statement) = self.declaration() {
+                statements.push(statement);
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
+        Ok(statements)
+    }
+
+    // for loops are desugared into an equivalent while loop wrapped in blocks
+    fn for_statement(&mut self) -> Result<Stmt, LoxError> {
+        let start = self.previous().span;
+        self.consume(TokenType::LeftParens, "Expect '(' after 'for'.")?;
+
+        let initializer = if self.match_token(&[TokenType::SemiColon]) {
+            None
+        } else if self.match_token(&[TokenType::Var]) {
+            Some(self.var_declaration()?)
+        } else {
+            Some(self.expression_statement()?)
+        };
+
+        let condition = if self.check(&TokenType::SemiColon) {
+            Expr::Literal(LiteralExpr {
+                value: Literal::Boolean(true),
+                span: self.peek().span,
+            })
+        } else {
+            self.expression()?
+        };
+        self.consume(TokenType::SemiColon, "Expect ';' after loop condition.")?;
+
+        let increment = if self.check(&TokenType::RightParens) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::RightParens, "Expect ')' after for clauses.")?;
+
+        let mut body = self.statement()?;
+        let span = self.span_from(start);
+
+        if let Some(expression) = increment {
+            let increment = Stmt::Expression(ExpressionStmt {
+                span: expression.span(),
+                expression,
+            });
+            body = Stmt::Block(BlockStmt {
+                statements: vec![body, increment],
+                span,
+            });
+        }
+
+        body = Stmt::While(WhileStmt {
+            condition,
+            body: Box::new(body),
+            span,
+        });
+
+        if let Some(initializer) = initializer {
+            body = Stmt::Block(BlockStmt {
+                statements: vec![initializer, body],
+                span,
+            });
+        }
+
+        Ok(body)
     }
 
-    pub fn parse(&mut self) -> Result<Expr, LoxError> {
-        self.expression()
+    fn if_statement(&mut self) -> Result<Stmt, LoxError> {
+        let start = self.previous().span;
+        self.consume(TokenType::LeftParens, "Expect '(' after 'if'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParens, "Expect ')' after if condition.")?;
+
+        let then_branch = Box::new(self.statement()?);
+        let mut else_branch = None;
+        if self.match_token(&[TokenType::Else]) {
+            else_branch = Some(Box::new(self.statement()?));
+        }
+
+        Ok(Stmt::If(IfStmt {
+            condition,
+            then_branch,
+            else_branch,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn while_statement(&mut self) -> Result<Stmt, LoxError> {
+        let start = self.previous().span;
+        self.consume(TokenType::LeftParens, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParens, "Expect ')' after condition.")?;
+        let body = Box::new(self.statement()?);
+
+        Ok(Stmt::While(WhileStmt {
+            condition,
+            body,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn print_statement(&mut self) -> Result<Stmt, LoxError> {
+        let start = self.previous().span;
+        let expression = self.expression()?;
+        self.consume(TokenType::SemiColon, "Expect ';' after value.")?;
+        Ok(Stmt::Print(PrintStmt {
+            expression,
+            span: self.span_from(start),
+        }))
+    }
+
+    fn return_statement(&mut self) -> Result<Stmt, LoxError> {
+        let keyword = self.previous().clone();
+        let mut value = None;
+        if !self.check(&TokenType::SemiColon) {
+            value = Some(self.expression()?);
+        }
+
+        self.consume(TokenType::SemiColon, "Expect ';' after return value.")?;
+        Ok(Stmt::Return(ReturnStmt {
+            span: self.span_from(keyword.span),
+            keyword,
+            value,
+        }))
+    }
+
+    fn expression_statement(&mut self) -> Result<Stmt, LoxError> {
+        let expression = self.expression()?;
+        self.consume(TokenType::SemiColon, "Expect ';' after expression.")?;
+        Ok(Stmt::Expression(ExpressionStmt {
+            span: self.span_from(expression.span()),
+            expression,
+        }))
     }
 
     fn equality(&mut self) -> Result<Expr, LoxError> {
         let mut expr = self.comparison()?;
 
-        while self.match_token(&vec![TokenType::BangEqual, TokenType::EqualEqual]) {
+        while self.match_token(&[TokenType::BangEqual, TokenType::EqualEqual]) {
             let operator = self.previous().clone();
             let right = Box::new(self.comparison()?);
             let left = Box::new(expr);
             expr = Expr::Binary(BinaryExpr {
+                span: left.span().to(right.span()),
                 left,
                 operator,
                 right,
@@ -53,7 +444,7 @@ impl Parser {
     fn comparison(&mut self) -> Result<Expr, LoxError> {
         let mut expr = self.term()?;
 
-        while self.match_token(&vec![
+        while self.match_token(&[
             TokenType::Greater,
             TokenType::GreaterEqual,
             TokenType::Less,
@@ -64,6 +455,7 @@ impl Parser {
             let left = Box::new(expr);
 
             expr = Expr::Binary(BinaryExpr {
+                span: left.span().to(right.span()),
                 left,
                 operator,
                 right,
@@ -76,12 +468,13 @@ impl Parser {
     fn term(&mut self) -> Result<Expr, LoxError> {
         let mut expr = self.factor()?;
 
-        while self.match_token(&vec![TokenType::Minus, TokenType::Plus]) {
+        while self.match_token(&[TokenType::Minus, TokenType::Plus]) {
             let operator = self.previous().clone();
             let right = Box::new(self.factor()?);
             let left = Box::new(expr);
 
             expr = Expr::Binary(BinaryExpr {
+                span: left.span().to(right.span()),
                 left,
                 operator,
                 right,
@@ -94,12 +487,13 @@ impl Parser {
     fn factor(&mut self) -> Result<Expr, LoxError> {
         let mut expr = self.unary()?;
 
-        while self.match_token(&vec![TokenType::Slash, TokenType::Star]) {
+        while self.match_token(&[TokenType::Slash, TokenType::Star, TokenType::Percent]) {
             let operator = self.previous().clone();
             let right = Box::new(self.unary()?);
             let left = Box::new(expr);
 
             expr = Expr::Binary(BinaryExpr {
+                span: left.span().to(right.span()),
                 left,
                 operator,
                 right,
@@ -110,51 +504,319 @@ impl Parser {
     }
 
     fn unary(&mut self) -> Result<Expr, LoxError> {
-        if self.match_token(&vec![TokenType::Bang, TokenType::Minus]) {
+        if self.match_token(&[TokenType::Bang, TokenType::Minus]) {
             let operator = self.previous().clone();
             let right = Box::new(self.unary()?);
-            return Ok(Expr::Unary(UnaryExpr { operator, right }));
+            return Ok(Expr::Unary(UnaryExpr {
+                span: operator.span.to(right.span()),
+                operator,
+                right,
+            }));
+        }
+        if self.match_token(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().clone();
+            let target = self.unary()?;
+            let span = operator.span.to(target.span());
+            return Ok(Expr::Update(UpdateExpr {
+                target: Self::update_target(target, &operator, "Invalid increment target.")?,
+                operator,
+                prefix: true,
+                span,
+            }));
+        }
+
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, LoxError> {
+        let expr = self.call()?;
+
+        if self.match_token(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous().clone();
+            let span = expr.span().to(operator.span);
+            return Ok(Expr::Update(UpdateExpr {
+                target: Self::update_target(expr, &operator, "Invalid increment target.")?,
+                operator,
+                prefix: false,
+                span,
+            }));
+        }
+
+        Ok(expr)
+    }
+
+    fn call(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.primary()?;
+
+        loop {
+            if self.match_token(&[TokenType::LeftParens]) {
+                expr = self.finish_call(expr)?;
+            } else if self.match_token(&[TokenType::Dot]) {
+                let name = self
+                    .consume(TokenType::Identifer, "Expect property name after '.'.")?
+                    .clone();
+                expr = Expr::Get(GetExpr {
+                    span: expr.span().to(name.span),
+                    object: Box::new(expr),
+                    name,
+                });
+            } else {
+                break;
+            }
         }
 
-        self.primary()
+        Ok(expr)
+    }
+
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr, LoxError> {
+        let mut arguments = Vec::new();
+        if !self.check(&TokenType::RightParens) {
+            loop {
+                if arguments.len() >= MAX_ARGUMENTS {
+                    return Err(LoxError::ParserError(
+                        format!("Can't have more than {} arguments.", MAX_ARGUMENTS),
+                        self.peek().clone(),
+                    ));
+                }
+                arguments.push(self.expression()?);
+
+                if !self.match_token(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        let paren = self
+            .consume(TokenType::RightParens, "Expect ')' after arguments.")?
+            .clone();
+
+        Ok(Expr::Call(CallExpr {
+            span: callee.span().to(paren.span),
+            callee: Box::new(callee),
+            paren,
+            arguments,
+        }))
     }
 
     // refactor to use match?
     fn primary(&mut self) -> Result<Expr, LoxError> {
-        if self.match_token(&vec![TokenType::False]) {
-            return Ok(Expr::Literal(Literal::Boolean(false)));
+        if self.match_token(&[TokenType::False]) {
+            return Ok(self.literal(Literal::Boolean(false)));
         }
-        if self.match_token(&vec![TokenType::True]) {
-            return Ok(Expr::Literal(Literal::Boolean(true)));
+        if self.match_token(&[TokenType::True]) {
+            return Ok(self.literal(Literal::Boolean(true)));
         }
-        if self.match_token(&vec![TokenType::Nil]) {
-            return Ok(Expr::Literal(Literal::Nil));
+        if self.match_token(&[TokenType::Nil]) {
+            return Ok(self.literal(Literal::Nil));
         }
-        if self.match_token(&vec![TokenType::Number]) {
+        if self.match_token(&[TokenType::Number]) {
             if let Some(token::Literal::Number(value)) = self.previous().literal {
-                return Ok(Expr::Literal(Literal::Number(value)));
+                return Ok(self.literal(Literal::Number(value)));
             }
         }
-        if self.match_token(&vec![TokenType::String]) {
+        if self.match_token(&[TokenType::String]) {
             if let Some(token::Literal::Str(value)) = &self.previous().literal {
-                return Ok(Expr::Literal(Literal::String(value.into())));
+                let value = Literal::String(value.into());
+                return Ok(self.literal(value));
             }
         }
-        if self.match_token(&vec![TokenType::LeftParens]) {
+        if self.match_token(&[TokenType::Interpolation]) {
+            return self.interpolation();
+        }
+        if self.match_token(&[TokenType::Super]) {
+            let keyword = self.previous().clone();
+            self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
+            let method = self
+                .consume(TokenType::Identifer, "Expect superclass method name.")?
+                .clone();
+            return Ok(Expr::Super(SuperExpr {
+                id: next_expr_id(),
+                span: keyword.span.to(method.span),
+                keyword,
+                method,
+            }));
+        }
+        if self.match_token(&[TokenType::This]) {
+            let keyword = self.previous().clone();
+            return Ok(Expr::This(ThisExpr {
+                id: next_expr_id(),
+                span: keyword.span,
+                keyword,
+            }));
+        }
+        if self.match_token(&[TokenType::Identifer]) {
+            let name = self.previous().clone();
+            return Ok(Expr::Variable(VariableExpr {
+                id: next_expr_id(),
+                span: name.span,
+                name,
+            }));
+        }
+        if self.match_token(&[TokenType::LeftParens]) {
+            let start = self.previous().span;
             let expression = Box::new(self.expression()?);
-            self.consume(TokenType::RightParens, "Expect '(' after expression")?;
-            return Ok(Expr::Grouping(GroupingExpr { expression }));
+            self.consume(TokenType::RightParens, "Expect ')' after expression.")?;
+            return Ok(Expr::Grouping(GroupingExpr {
+                expression,
+                span: self.span_from(start),
+            }));
         }
 
         Err(LoxError::ParserError(
             "Expect expression.".into(),
-            self.curr,
             self.peek().clone(),
         ))
     }
 
+    // the string segments and interpolated expressions of `"a ${b} c"` in
+    // order, leaving out empty segments
+    fn interpolation(&mut self) -> Result<Expr, LoxError> {
+        let start = self.previous().span;
+        let mut parts = Vec::new();
+        loop {
+            if !self.previous().lexeme.is_empty() {
+                parts.push(self.string_segment());
+            }
+            if self.previous().token_type == TokenType::String {
+                return Ok(Expr::Interpolation(InterpolationExpr {
+                    parts,
+                    span: self.span_from(start),
+                }));
+            }
+
+            parts.push(self.expression()?);
+
+            if !self.match_token(&[TokenType::Interpolation]) {
+                self.consume(
+                    TokenType::String,
+                    "Expect '}' after interpolated expression.",
+                )?;
+            }
+        }
+    }
+
+    fn string_segment(&self) -> Expr {
+        let value = match &self.previous().literal {
+            Some(token::Literal::Str(value)) => value.clone(),
+            _ => String::new(),
+        };
+        self.literal(Literal::String(value))
+    }
+
+    // literal expression spanning the token just consumed
+    fn literal(&self, value: Literal) -> Expr {
+        Expr::Literal(LiteralExpr {
+            value,
+            span: self.previous().span,
+        })
+    }
+
     fn expression(&mut self) -> Result<Expr, LoxError> {
-        self.equality()
+        self.assignment()
+    }
+
+    fn assignment(&mut self) -> Result<Expr, LoxError> {
+        let expr = self.or()?;
+
+        if self.match_token(&[TokenType::Equal]) {
+            let equals = self.previous().clone();
+            let value = self.assignment()?;
+            let span = expr.span().to(value.span());
+            return Self::assign_to(expr, value, span, equals, "Invalid assignment target.");
+        }
+
+        if self.match_token(&[
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+        ]) {
+            let operator = self.previous().clone();
+            let value = self.assignment()?;
+            let span = expr.span().to(value.span());
+            return Ok(Expr::CompoundAssign(CompoundAssignExpr {
+                target: Self::update_target(expr, &operator, "Invalid assignment target.")?,
+                operator,
+                value: Box::new(value),
+                span,
+            }));
+        }
+
+        Ok(expr)
+    }
+
+    // builds the assignment of `value` to `target`, which must be a variable
+    // or a property access
+    fn assign_to(
+        target: Expr,
+        value: Expr,
+        span: Span,
+        operator: Token,
+        msg: &str,
+    ) -> Result<Expr, LoxError> {
+        let value = Box::new(value);
+        match target {
+            Expr::Variable(VariableExpr { name, .. }) => Ok(Expr::Assign(AssignExpr {
+                id: next_expr_id(),
+                name,
+                value,
+                span,
+            })),
+            Expr::Get(GetExpr { object, name, .. }) => Ok(Expr::Set(SetExpr {
+                object,
+                name,
+                value,
+                span,
+            })),
+            _ => Err(LoxError::ParserError(msg.into(), operator)),
+        }
+    }
+
+    // the target of an increment or compound assignment, which is read and
+    // written in place so must be a variable or a property access
+    fn update_target(target: Expr, operator: &Token, msg: &str) -> Result<Box<Expr>, LoxError> {
+        match target {
+            Expr::Variable(_) | Expr::Get(_) => Ok(Box::new(target)),
+            _ => Err(LoxError::ParserError(msg.into(), operator.clone())),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.and()?;
+
+        while self.match_token(&[TokenType::Or]) {
+            let operator = self.previous().clone();
+            let right = Box::new(self.and()?);
+            let left = Box::new(expr);
+
+            expr = Expr::Logical(LogicalExpr {
+                span: left.span().to(right.span()),
+                left,
+                operator,
+                right,
+            })
+        }
+
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.equality()?;
+
+        while self.match_token(&[TokenType::And]) {
+            let operator = self.previous().clone();
+            let right = Box::new(self.equality()?);
+            let left = Box::new(expr);
+
+            expr = Expr::Logical(LogicalExpr {
+                span: left.span().to(right.span()),
+                left,
+                operator,
+                right,
+            })
+        }
+
+        Ok(expr)
     }
 
     fn consume(&mut self, token_type: TokenType, msg: &str) -> Result<&Token, LoxError> {
@@ -162,11 +824,7 @@ impl Parser {
             return Ok(self.advance());
         }
 
-        Err(LoxError::ParserError(
-            msg.into(),
-            self.curr,
-            self.peek().clone(),
-        ))
+        Err(LoxError::ParserError(msg.into(), self.peek().clone()))
     }
 
     fn synchronize(&mut self) {
@@ -205,38 +863,173 @@ impl Parser {
 
     fn advance(&mut self) -> &Token {
         if !self.is_at_end() {
-            self.curr += 1;
+            // a source that runs dry without an `Eof` is treated as ending there
+            let next = self
+                .tokens
+                .next()
+                .unwrap_or_else(|| Token::new(TokenType::Eof, "".into(), None, self.current.span));
+            self.previous = std::mem::replace(&mut self.current, next);
         }
-        return self.previous();
+        self.previous()
     }
 
     fn check(&self, token_type: &TokenType) -> bool {
         if self.is_at_end() {
             return false;
         }
-        return self.peek().token_type == *token_type;
+        self.peek().token_type == *token_type
     }
 
     fn is_at_end(&self) -> bool {
-        return self.peek().token_type == TokenType::Eof;
+        self.peek().token_type == TokenType::Eof
     }
 
     fn peek(&self) -> &Token {
-        &self.tokens[self.curr]
+        &self.current
     }
 
     fn previous(&self) -> &Token {
-        &self.tokens[self.curr - 1]
+        &self.previous
     }
+
+    // span from `start` through the end of the token just consumed
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.previous().span)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Stmt {
+    Block(BlockStmt),
+    Class(ClassStmt),
+    Expression(ExpressionStmt),
+    Function(FunctionStmt),
+    If(IfStmt),
+    Print(PrintStmt),
+    Return(ReturnStmt),
+    Var(VarStmt),
+    While(WhileStmt),
+}
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Block(s) => s.span,
+            Stmt::Class(s) => s.span,
+            Stmt::Expression(s) => s.span,
+            Stmt::Function(s) => s.span,
+            Stmt::If(s) => s.span,
+            Stmt::Print(s) => s.span,
+            Stmt::Return(s) => s.span,
+            Stmt::Var(s) => s.span,
+            Stmt::While(s) => s.span,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockStmt {
+    pub statements: Vec<Stmt>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClassStmt {
+    pub name: Token,
+    pub superclass: Option<VariableExpr>,
+    pub methods: Vec<FunctionStmt>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExpressionStmt {
+    pub expression: Expr,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionStmt {
+    pub name: Token,
+    pub params: Vec<Token>,
+    pub body: Rc<Vec<Stmt>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct IfStmt {
+    pub condition: Expr,
+    pub then_branch: Box<Stmt>,
+    pub else_branch: Option<Box<Stmt>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct PrintStmt {
+    pub expression: Expr,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReturnStmt {
+    pub keyword: Token,
+    pub value: Option<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct VarStmt {
+    pub name: Token,
+    pub initializer: Option<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct WhileStmt {
+    pub condition: Expr,
+    pub body: Box<Stmt>,
+    pub span: Span,
 }
 
 // encapsulate data directly in enum or in struct?
 #[derive(Debug, Clone)]
 pub enum Expr {
+    Assign(AssignExpr),
     Binary(BinaryExpr),
+    Call(CallExpr),
+    CompoundAssign(CompoundAssignExpr),
+    Get(GetExpr),
     Grouping(GroupingExpr),
-    Literal(Literal),
+    Interpolation(InterpolationExpr),
+    Literal(LiteralExpr),
+    Logical(LogicalExpr),
+    Set(SetExpr),
+    Super(SuperExpr),
+    This(ThisExpr),
     Unary(UnaryExpr),
+    Update(UpdateExpr),
+    Variable(VariableExpr),
+}
+
+impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Assign(e) => e.span,
+            Expr::Binary(e) => e.span,
+            Expr::Call(e) => e.span,
+            Expr::CompoundAssign(e) => e.span,
+            Expr::Get(e) => e.span,
+            Expr::Grouping(e) => e.span,
+            Expr::Interpolation(e) => e.span,
+            Expr::Literal(e) => e.span,
+            Expr::Logical(e) => e.span,
+            Expr::Set(e) => e.span,
+            Expr::Super(e) => e.span,
+            Expr::This(e) => e.span,
+            Expr::Unary(e) => e.span,
+            Expr::Update(e) => e.span,
+            Expr::Variable(e) => e.span,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -247,22 +1040,118 @@ pub enum Literal {
     Nil,
 }
 
+#[derive(Debug, Clone)]
+pub struct AssignExpr {
+    pub id: usize,
+    pub name: Token,
+    pub value: Box<Expr>,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone)]
 pub struct BinaryExpr {
     pub left: Box<Expr>,
     pub operator: Token,
     pub right: Box<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct CallExpr {
+    pub callee: Box<Expr>,
+    pub paren: Token,
+    pub arguments: Vec<Expr>,
+    pub span: Span,
+}
+
+// `target op= value`, with the target evaluated once
+#[derive(Debug, Clone)]
+pub struct CompoundAssignExpr {
+    pub target: Box<Expr>,
+    pub operator: Token,
+    pub value: Box<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct GetExpr {
+    pub object: Box<Expr>,
+    pub name: Token,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct GroupingExpr {
     pub expression: Box<Expr>,
+    pub span: Span,
+}
+
+// the pieces of an interpolated string, each converted to a string and joined
+#[derive(Debug, Clone)]
+pub struct InterpolationExpr {
+    pub parts: Vec<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct LiteralExpr {
+    pub value: Literal,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct LogicalExpr {
+    pub left: Box<Expr>,
+    pub operator: Token,
+    pub right: Box<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct SetExpr {
+    pub object: Box<Expr>,
+    pub name: Token,
+    pub value: Box<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct SuperExpr {
+    pub id: usize,
+    pub keyword: Token,
+    pub method: Token,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct ThisExpr {
+    pub id: usize,
+    pub keyword: Token,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct UnaryExpr {
     pub operator: Token,
     pub right: Box<Expr>,
+    pub span: Span,
+}
+
+// `++target` or `target--`, yielding the new value when prefix and the old
+// one otherwise
+#[derive(Debug, Clone)]
+pub struct UpdateExpr {
+    pub target: Box<Expr>,
+    pub operator: Token,
+    pub prefix: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct VariableExpr {
+    pub id: usize,
+    pub name: Token,
+    pub span: Span,
 }
 
 /*
@@ -273,21 +1162,32 @@ pub struct UnaryExpr {
         left: Box::new(Expr::Unary(UnaryExpr {
             operator: Token{
                 token_type: TokenType::Minus,
-                lexeme: "-".to_string(),
+                lexeme: "-".into(),
                 literal: None,
-                line: 1,
+                span: Span::default(),
+                trivia: Box::default(),
             },
-            right: Box::new(Expr::Literal(Literal::Number(123.0))),
+            right: Box::new(Expr::Literal(LiteralExpr {
+                value: Literal::Number(123.0),
+                span: Span::default(),
+            })),
+            span: Span::default(),
         })),
         operator: Token {
             token_type: TokenType::Star,
-            lexeme: "*".to_string(),
+            lexeme: "*".into(),
             literal: None,
-            line: 1,
+            span: Span::default(),
+            trivia: Box::default(),
         },
         right: Box::new(Expr::Grouping(GroupingExpr{
-            expression: Box::new(Expr::Literal(Literal::Number(35.67)))
-        }))
+            expression: Box::new(Expr::Literal(LiteralExpr {
+                value: Literal::Number(35.67),
+                span: Span::default(),
+            })),
+            span: Span::default(),
+        })),
+        span: Span::default(),
     });
 
     println!("{:?}", expression);
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use crate::{
+    error::LoxError,
+    interpreter::Interpreter,
+    parser::{
+        AssignExpr, BlockStmt, ClassStmt, FunctionStmt, ReturnStmt, Stmt, SuperExpr, ThisExpr,
+        VarStmt, VariableExpr,
+    },
+    token::Token,
+    visitor::{self, Visitor},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Initializer,
+    Method,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+/// Static pass run between parsing and interpreting. Records the scope depth
+/// of every local variable reference on the interpreter and reports misuse of
+/// variables, `return`, `this` and `super`.
+pub struct Resolver<'a> {
+    interpreter: &'a mut Interpreter,
+    // value is whether the variable has finished being initialized
+    scopes: Vec<HashMap<String, bool>>,
+    current_function: FunctionType,
+    current_class: ClassType,
+    errors: Vec<LoxError>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(interpreter: &'a mut Interpreter) -> Self {
+        Resolver {
+            interpreter,
+            scopes: Vec::new(),
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn resolve(mut self, statements: &[Stmt]) -> Result<(), Vec<LoxError>> {
+        for statement in statements {
+            self.visit_stmt(statement);
+        }
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn resolve_function(&mut self, function: &FunctionStmt, function_type: FunctionType) {
+        let enclosing_function = self.current_function;
+        self.current_function = function_type;
+
+        self.begin_scope();
+        for param in &function.params {
+            self.declare(param);
+            self.define(param);
+        }
+        for statement in function.body.iter() {
+            self.visit_stmt(statement);
+        }
+        self.end_scope();
+
+        self.current_function = enclosing_function;
+    }
+
+    fn resolve_local(&mut self, id: usize, name: &Token) {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key(&*name.lexeme) {
+                self.interpreter.resolve(id, depth);
+                return;
+            }
+        }
+        // not found in any scope, assume it is global
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &Token) {
+        let already_declared = match self.scopes.last_mut() {
+            Some(scope) => scope.insert(name.lexeme.to_string(), false).is_some(),
+            None => return,
+        };
+
+        if already_declared {
+            self.error(name, "Already a variable with this name in this scope.");
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        self.scope_insert(&name.lexeme);
+    }
+
+    fn scope_insert(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), true);
+        }
+    }
+
+    fn error(&mut self, token: &Token, msg: &str) {
+        self.errors
+            .push(LoxError::ResolverError(msg.into(), token.clone()));
+    }
+}
+
+// only the nodes that declare or look up names, or can be misused, need more
+// than the default traversal
+impl Visitor<()> for Resolver<'_> {
+    fn default_result(&mut self) {}
+
+    fn visit_block_stmt(&mut self, stmt: &BlockStmt) {
+        self.begin_scope();
+        visitor::walk_block_stmt(self, stmt);
+        self.end_scope();
+    }
+
+    fn visit_class_stmt(&mut self, stmt: &ClassStmt) {
+        let ClassStmt {
+            name,
+            superclass,
+            methods,
+            ..
+        } = stmt;
+        let enclosing_class = self.current_class;
+        self.current_class = ClassType::Class;
+
+        self.declare(name);
+        self.define(name);
+
+        if let Some(superclass) = superclass {
+            if superclass.name.lexeme == name.lexeme {
+                self.error(&superclass.name, "A class can't inherit from itself.");
+            }
+
+            self.current_class = ClassType::Subclass;
+            self.resolve_local(superclass.id, &superclass.name);
+
+            self.begin_scope();
+            self.scope_insert("super");
+        }
+
+        self.begin_scope();
+        self.scope_insert("this");
+
+        for method in methods {
+            let declaration = if &*method.name.lexeme == "init" {
+                FunctionType::Initializer
+            } else {
+                FunctionType::Method
+            };
+            self.resolve_function(method, declaration);
+        }
+
+        self.end_scope();
+
+        if superclass.is_some() {
+            self.end_scope();
+        }
+
+        self.current_class = enclosing_class;
+    }
+
+    fn visit_function_stmt(&mut self, stmt: &FunctionStmt) {
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+
+        self.resolve_function(stmt, FunctionType::Function);
+    }
+
+    fn visit_return_stmt(&mut self, stmt: &ReturnStmt) {
+        let ReturnStmt { keyword, value, .. } = stmt;
+        if self.current_function == FunctionType::None {
+            self.error(keyword, "Can't return from top-level code.");
+        }
+
+        if value.is_some() && self.current_function == FunctionType::Initializer {
+            self.error(keyword, "Can't return a value from an initializer.");
+        }
+        visitor::walk_return_stmt(self, stmt);
+    }
+
+    fn visit_var_stmt(&mut self, stmt: &VarStmt) {
+        self.declare(&stmt.name);
+        visitor::walk_var_stmt(self, stmt);
+        self.define(&stmt.name);
+    }
+
+    fn visit_assign_expr(&mut self, expr: &AssignExpr) {
+        visitor::walk_assign_expr(self, expr);
+        self.resolve_local(expr.id, &expr.name);
+    }
+
+    fn visit_super_expr(&mut self, expr: &SuperExpr) {
+        let SuperExpr { id, keyword, .. } = expr;
+        match self.current_class {
+            ClassType::None => self.error(keyword, "Can't use 'super' outside of a class."),
+            ClassType::Class => {
+                self.error(keyword, "Can't use 'super' in a class with no superclass.")
+            }
+            ClassType::Subclass => (),
+        }
+        self.resolve_local(*id, keyword);
+    }
+
+    fn visit_this_expr(&mut self, expr: &ThisExpr) {
+        let ThisExpr { id, keyword, .. } = expr;
+        if self.current_class == ClassType::None {
+            self.error(keyword, "Can't use 'this' outside of a class.");
+            return;
+        }
+        self.resolve_local(*id, keyword);
+    }
+
+    fn visit_variable_expr(&mut self, expr: &VariableExpr) {
+        let VariableExpr { id, name, .. } = expr;
+        if let Some(scope) = self.scopes.last() {
+            if scope.get(&*name.lexeme) == Some(&false) {
+                self.error(name, "Can't read local variable in its own initializer.");
+            }
+        }
+        self.resolve_local(*id, name);
+    }
+}
@@ -1,73 +1,194 @@
-use itertools::Itertools;
-use itertools::MultiPeek;
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::str::{Chars, FromStr};
+use std::rc::Rc;
+use std::str::FromStr;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+use unicode_xid::UnicodeXID;
 
+use crate::error::LoxError;
 use crate::token::Literal;
-use crate::token::{Token, TokenType};
+use crate::token::{Span, Token, TokenType, Trivia, TriviaKind};
 
+/// Scans tokens straight out of the source text, tracking positions as byte
+/// offsets into it. Lexemes are interned so every token spelled the same way
+/// shares a single allocation.
+///
+/// Tokens are produced lazily by iterating over the scanner, ending with a
+/// single `Eof` token. Lexical errors are collected along the way and can be
+/// retrieved with `take_errors`.
+///
+/// A lossless scanner also attaches the whitespace, comments and invalid input
+/// preceding each token as its trivia, with whatever is left at the end of the
+/// source attached to the `Eof` token, so the tokens cover the whole source.
 pub struct Scanner<'a> {
-    source: MultiPeek<Chars<'a>>,
-    curr_buf: Vec<char>,
+    source: &'a str,
     line: u32,
-    keywords: HashMap<String, TokenType>,
+    // characters consumed so far on the current line
+    column: u32,
+    // bytes consumed so far
+    offset: usize,
+    // position of the first character of the token being scanned
+    start: usize,
+    start_line: u32,
+    start_column: u32,
+    keywords: HashMap<&'static str, TokenType>,
+    interner: HashMap<Cow<'a, str>, Rc<str>>,
+    // one entry per `${` still open, holding the number of unclosed braces
+    // inside it and the span of the `${` for error reporting
+    interpolations: Vec<(usize, Span)>,
+    errors: Vec<LoxError>,
+    finished: bool,
+    lossless: bool,
+    // trivia seen since the last token
+    trivia: Vec<Trivia>,
 }
 
 impl<'a> Scanner<'a> {
     pub fn new(source: &'a str) -> Self {
         Self {
-            source: source.chars().multipeek(),
-            curr_buf: Vec::new(),
-            line: 1 as u32,
+            source,
+            line: 1,
+            column: 0,
+            offset: 0,
+            start: 0,
+            start_line: 1,
+            start_column: 1,
             keywords: HashMap::from_iter([
-                ("and".to_string(), TokenType::And),
-                ("class".to_string(), TokenType::Class),
-                ("else".to_string(), TokenType::Else),
-                ("false".to_string(), TokenType::False),
-                ("for".to_string(), TokenType::For),
-                ("fun".to_string(), TokenType::Fun),
-                ("if".to_string(), TokenType::If),
-                ("nil".to_string(), TokenType::Nil),
-                ("or".to_string(), TokenType::Or),
-                ("print".to_string(), TokenType::Print),
-                ("return".to_string(), TokenType::Return),
-                ("super".to_string(), TokenType::Super),
-                ("this".to_string(), TokenType::This),
-                ("true".to_string(), TokenType::True),
-                ("var".to_string(), TokenType::Var),
-                ("while".to_string(), TokenType::While),
+                ("and", TokenType::And),
+                ("class", TokenType::Class),
+                ("else", TokenType::Else),
+                ("false", TokenType::False),
+                ("for", TokenType::For),
+                ("fun", TokenType::Fun),
+                ("if", TokenType::If),
+                ("nil", TokenType::Nil),
+                ("or", TokenType::Or),
+                ("print", TokenType::Print),
+                ("return", TokenType::Return),
+                ("super", TokenType::Super),
+                ("this", TokenType::This),
+                ("true", TokenType::True),
+                ("var", TokenType::Var),
+                ("while", TokenType::While),
             ]),
+            interner: HashMap::new(),
+            interpolations: Vec::new(),
+            errors: Vec::new(),
+            finished: false,
+            lossless: false,
+            trivia: Vec::new(),
         }
     }
 
-    pub fn scan_tokens(&mut self) -> Vec<Token> {
-        let mut tokens = Vec::new();
-        loop {
-            if let Some(token) = self.scan_token() {
-                if token.token_type != TokenType::Ignore {
-                    tokens.push(token);
-                }
-            } else {
-                break;
-            }
+    /// Scanner keeping trivia attached to the tokens it produces.
+    pub fn lossless(source: &'a str) -> Self {
+        Self {
+            lossless: true,
+            ..Self::new(source)
         }
+    }
 
-        tokens.push(Token::new(TokenType::Eof, "".to_string(), None, self.line));
-        tokens
+    /// Scanner starting at byte `offset` of `source`, at the beginning of line
+    /// `line`, so positions of the tokens it produces stay relative to the
+    /// whole source. The REPL scans each input this way against everything
+    /// entered so far.
+    pub fn starting_at(source: &'a str, offset: usize, line: u32) -> Self {
+        Self {
+            line,
+            offset,
+            start: offset,
+            start_line: line,
+            ..Self::new(source)
+        }
+    }
+
+    /// Scans the whole source, returning the tokens alongside any lexical
+    /// errors. Invalid input is reported as an error and left out of the
+    /// token stream.
+    pub fn scan_tokens(&mut self) -> (Vec<Token>, Vec<LoxError>) {
+        let tokens = self.by_ref().collect();
+        (tokens, self.take_errors())
+    }
+
+    /// Lexical errors found in the tokens scanned so far.
+    pub fn take_errors(&mut self) -> Vec<LoxError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn eof(&mut self) -> Token {
+        for (_, span) in std::mem::take(&mut self.interpolations) {
+            self.error_at(span, "Unterminated string interpolation.");
+        }
+
+        let span = Span::new(self.offset, self.offset, self.line, self.column + 1);
+        let lexeme = self.intern(Cow::Borrowed(""));
+        Token {
+            trivia: std::mem::take(&mut self.trivia).into_boxed_slice(),
+            ..Token::new(TokenType::Eof, lexeme, None, span)
+        }
     }
 
+    // scans the next token, or an `Ignore` token for comments and invalid
+    // input, returning `None` once the source is exhausted
     fn scan_token(&mut self) -> Option<Token> {
+        self.mark_start();
+        self.advance_until(|c| matches!(c, ' ' | '\r' | '\t' | '\n'));
+        if self.offset > self.start {
+            self.record_trivia(TriviaKind::Whitespace);
+            self.mark_start();
+        }
+
         let c = self.advance()?;
         let token = match c {
             '(' => self.finalize_token(TokenType::LeftParens),
             ')' => self.finalize_token(TokenType::RightParens),
-            '{' => self.finalize_token(TokenType::LeftBrace),
-            '}' => self.finalize_token(TokenType::RightBrace),
+            '{' => {
+                if let Some((depth, _)) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.finalize_token(TokenType::LeftBrace)
+            }
+            '}' => match self.interpolations.last_mut() {
+                // closes the innermost `${`, the string carries on from here
+                Some((0, _)) => {
+                    self.interpolations.pop();
+                    self.string()
+                }
+                Some((depth, _)) => {
+                    *depth -= 1;
+                    self.finalize_token(TokenType::RightBrace)
+                }
+                None => self.finalize_token(TokenType::RightBrace),
+            },
             ',' => self.finalize_token(TokenType::Comma),
             '.' => self.finalize_token(TokenType::Dot),
-            '-' => self.finalize_token(TokenType::Minus),
             ';' => self.finalize_token(TokenType::SemiColon),
-            '*' => self.finalize_token(TokenType::Star),
+            '%' => self.finalize_token(TokenType::Percent),
+            '+' => {
+                if self.advance_on_match('+') {
+                    self.finalize_token(TokenType::PlusPlus)
+                } else if self.advance_on_match('=') {
+                    self.finalize_token(TokenType::PlusEqual)
+                } else {
+                    self.finalize_token(TokenType::Plus)
+                }
+            }
+            '-' => {
+                if self.advance_on_match('-') {
+                    self.finalize_token(TokenType::MinusMinus)
+                } else if self.advance_on_match('=') {
+                    self.finalize_token(TokenType::MinusEqual)
+                } else {
+                    self.finalize_token(TokenType::Minus)
+                }
+            }
+            '*' => {
+                if self.advance_on_match('=') {
+                    self.finalize_token(TokenType::StarEqual)
+                } else {
+                    self.finalize_token(TokenType::Star)
+                }
+            }
             '!' => {
                 if self.advance_on_match('=') {
                     self.finalize_token(TokenType::BangEqual)
@@ -99,81 +220,274 @@ impl<'a> Scanner<'a> {
             '/' => {
                 if self.advance_on_match('/') {
                     self.advance_until(|c| c != &'\n');
-                    self.curr_buf.clear();
-                    self.scan_token()?
+                    self.skipped(TriviaKind::LineComment)
+                } else if self.advance_on_match('*') {
+                    self.block_comment()
+                } else if self.advance_on_match('=') {
+                    self.finalize_token(TokenType::SlashEqual)
                 } else {
                     self.finalize_token(TokenType::Slash)
                 }
             }
-            ' ' | '\r' | '\t' | '\n' => self.finalize_token(TokenType::Ignore),
             '"' => self.string(),
-            d if d.is_ascii_digit() => {
-                let next_char = self.advance_until(|c| c.is_ascii_digit());
-                if next_char == Some('.') {
-                    if let Some(c) = self.source.peek() {
-                        if c.is_ascii_digit() {
-                            self.advance();
-                            self.advance_until(|c| c.is_ascii_digit());
-                        }
-                    }
-                }
-                let lexeme = String::from_iter(self.curr_buf.drain(..));
-                match f64::from_str(&lexeme) {
-                    Ok(n) => Token {
-                        token_type: TokenType::Number,
-                        lexeme,
-                        literal: Some(Literal::Number(n)),
-                        line: self.line,
-                    },
-                    Err(_) => self.finalize_error_token(Some("Failed to parse number")),
-                }
-            }
-            c if c.is_ascii_alphabetic() || &c == &'_' => {
-                self.advance_until(|c| c.is_ascii_alphanumeric() || c == &'_');
+            d if d.is_ascii_digit() => self.number(d),
+            c if c.is_xid_start() || c == '_' => {
+                self.advance_until(|c| c.is_xid_continue());
 
-                let lexeme = String::from_iter(self.curr_buf.drain(..));
-                let token_type = match self.keywords.get(&lexeme) {
-                    Some(t) => t.clone(),
+                // identifiers spelled with different but canonically equivalent
+                // code points refer to the same variable
+                let raw = self.lexeme();
+                let lexeme = if raw.is_ascii() || is_nfc_quick(raw.chars()) == IsNormalized::Yes {
+                    Cow::Borrowed(raw)
+                } else {
+                    Cow::Owned(raw.nfc().collect())
+                };
+                let token_type = match self.keywords.get(&*lexeme) {
+                    Some(t) => *t,
                     None => TokenType::Identifer,
                 };
 
                 Token {
                     token_type,
-                    lexeme,
+                    lexeme: self.intern(lexeme),
                     literal: None,
-                    line: self.line,
+                    span: self.span(),
+                    trivia: Box::default(),
                 }
             }
-            _ => self.finalize_error_token(Some("Unexpected character.")),
+            _ => self.error("Unexpected character."),
         };
 
         Some(token)
     }
 
-    fn string(&mut self) -> Token {
-        if let Some(_) = self.advance_until(|c| c != &'"') {
-            // Consume last '"'
+    // decimal literals may have a fraction and an exponent, `0x` and `0b`
+    // prefixes introduce hexadecimal and binary integers. Any of them may use
+    // `_` between digits as a separator
+    fn number(&mut self, first: char) -> Token {
+        if first == '0' {
+            match self.peek() {
+                Some('x') | Some('X') => return self.radix_number(16, "hexadecimal"),
+                Some('b') | Some('B') => return self.radix_number(2, "binary"),
+                _ => {}
+            }
+        }
+
+        self.advance_until(|c| c.is_ascii_digit() || c == &'_');
+        if self.peek() == Some('.') && self.peek_next().is_some_and(|c| c.is_ascii_digit()) {
+            self.advance();
+            self.advance_until(|c| c.is_ascii_digit() || c == &'_');
+        }
+        if let Some('e') | Some('E') = self.peek() {
             self.advance();
-            let lexeme = String::from_iter(self.curr_buf.drain(..))
-                .trim_matches('"')
-                .to_string();
-            Token {
-                token_type: TokenType::String,
-                lexeme: lexeme.clone(),
-                literal: Some(Literal::Str(lexeme.clone())),
-                line: self.line,
+            if let Some('+') | Some('-') = self.peek() {
+                self.advance();
+            }
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return self.error("Expect digits in number exponent.");
+            }
+            self.advance_until(|c| c.is_ascii_digit() || c == &'_');
+        }
+
+        let lexeme = self.lexeme();
+        if !separators_between(lexeme, |c| c.is_ascii_digit()) {
+            return self.error("Digit separators must be placed between digits.");
+        }
+        match f64::from_str(&without_separators(lexeme)) {
+            Ok(n) => self.number_token(n),
+            Err(_) => self.error("Failed to parse number."),
+        }
+    }
+
+    fn radix_number(&mut self, radix: u32, name: &str) -> Token {
+        self.advance();
+        self.advance_until(|c| c.is_ascii_alphanumeric() || c == &'_');
+
+        let (prefix, digits) = self.lexeme().split_at(2);
+        if digits.is_empty() {
+            return self.error(&format!("Expect {} digits after '{}'.", name, prefix));
+        }
+        if let Some(c) = digits.chars().find(|c| c != &'_' && !c.is_digit(radix)) {
+            return self.error(&format!("Invalid digit '{}' in {} literal.", c, name));
+        }
+        if !separators_between(digits, |c| c.is_digit(radix)) {
+            return self.error("Digit separators must be placed between digits.");
+        }
+        match u64::from_str_radix(&without_separators(digits), radix) {
+            Ok(n) => self.number_token(n as f64),
+            Err(_) => self.error("Number literal is too large."),
+        }
+    }
+
+    fn number_token(&mut self, value: f64) -> Token {
+        Token {
+            literal: Some(Literal::Number(value)),
+            ..self.finalize_token(TokenType::Number)
+        }
+    }
+
+    // skips a `/* ... */` comment, which may contain nested block comments
+    fn block_comment(&mut self) -> Token {
+        let mut depth = 1;
+        while depth > 0 {
+            match (self.peek(), self.peek_next()) {
+                (None, _) => {
+                    // point at the opening delimiter rather than the whole file
+                    let span = Span::new(
+                        self.start,
+                        self.start + 2,
+                        self.start_line,
+                        self.start_column,
+                    );
+                    self.error_at(span, "Unterminated block comment.");
+                    break;
+                }
+                (Some('*'), Some('/')) => {
+                    self.advance();
+                    self.advance();
+                    depth -= 1;
+                }
+                (Some('/'), Some('*')) => {
+                    self.advance();
+                    self.advance();
+                    depth += 1;
+                }
+                _ => {
+                    self.advance();
+                }
+            }
+        }
+        self.skipped(TriviaKind::BlockComment)
+    }
+
+    // the token's lexeme is the raw text between the quotes while its literal
+    // holds the value with escape sequences processed. A string containing
+    // `${` is split into an `Interpolation` token for each segment ending in
+    // `${`, followed by the tokens of the embedded expression, and finally a
+    // `String` token for the rest once the matching `}` is reached
+    fn string(&mut self) -> Token {
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                None => return self.error("Unterminated string."),
+                Some('"') => break,
+                Some('$') if self.peek_next() == Some('{') => {
+                    let start = Span::new(self.offset, self.offset + 2, self.line, self.column + 1);
+                    self.advance();
+                    self.advance();
+                    self.interpolations.push((0, start));
+
+                    let raw = self.lexeme();
+                    return Token {
+                        token_type: TokenType::Interpolation,
+                        lexeme: self.intern(Cow::Borrowed(&raw[1..raw.len() - 2])),
+                        literal: Some(Literal::Str(value)),
+                        span: self.span(),
+                        trivia: Box::default(),
+                    };
+                }
+                Some('\\') => {
+                    let start = (self.offset, self.line, self.column + 1);
+                    self.advance();
+                    match self.escape() {
+                        Ok(Some(c)) => value.push(c),
+                        Ok(None) => {}
+                        Err(msg) => {
+                            let (offset, line, column) = start;
+                            let span = Span::new(offset, self.offset, line, column);
+                            self.error_at(span, msg);
+                        }
+                    }
+                }
+                Some(_) => {
+                    if let Some(c) = self.advance() {
+                        value.push(c);
+                    }
+                }
+            }
+        }
+
+        // Consume last '"'
+        self.advance();
+        let raw = self.lexeme();
+        Token {
+            token_type: TokenType::String,
+            lexeme: self.intern(Cow::Borrowed(&raw[1..raw.len() - 1])),
+            literal: Some(Literal::Str(value)),
+            span: self.span(),
+            trivia: Box::default(),
+        }
+    }
+
+    // decodes the escape sequence following a backslash, or nothing at the end
+    // of input, which is left to be reported as an unterminated string
+    fn escape(&mut self) -> Result<Option<char>, &'static str> {
+        match self.peek() {
+            Some('n') | Some('t') | Some('r') | Some('\\') | Some('"') | Some('$') => {
+                let c = self.advance().unwrap_or_default();
+                Ok(Some(match c {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    c => c,
+                }))
+            }
+            Some('u') => {
+                self.advance();
+                if self.peek() != Some('{') {
+                    return Err("Expect '{' after '\\u' in unicode escape.");
+                }
+                self.advance();
+
+                let mut digits = String::new();
+                while let Some(c) = self.peek().filter(|c| c.is_ascii_hexdigit()) {
+                    self.advance();
+                    digits.push(c);
+                }
+
+                if self.peek() != Some('}') {
+                    return Err("Expect '}' after unicode escape digits.");
+                }
+                self.advance();
+
+                if digits.is_empty() || digits.len() > 6 {
+                    return Err("Unicode escape must have between 1 and 6 hex digits.");
+                }
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .map(Some)
+                    .ok_or("Invalid unicode code point in escape.")
+            }
+            None => Ok(None),
+            Some(_) => {
+                self.advance();
+                Err("Invalid escape sequence.")
             }
-        } else {
-            self.finalize_error_token(Some("Unterminated string."))
         }
     }
 
+    fn rest(&self) -> &'a str {
+        &self.source[self.offset..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn peek_next(&self) -> Option<char> {
+        self.rest().chars().nth(1)
+    }
+
     fn advance(&mut self) -> Option<char> {
-        let c = self.source.next()?;
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
         if c == '\n' {
             self.line += 1;
+            self.column = 0;
+        } else {
+            self.column += 1;
         }
-        self.curr_buf.push(c);
         Some(c)
     }
 
@@ -182,17 +496,17 @@ impl<'a> Scanner<'a> {
         F: Fn(&char) -> bool,
     {
         loop {
-            let next = self.source.peek()?;
-            if f(next) {
+            let next = self.peek()?;
+            if f(&next) {
                 self.advance();
             } else {
-                return Some(*next);
+                return Some(next);
             }
         }
     }
 
     fn advance_on_match(&mut self, c: char) -> bool {
-        if Some(&c) == self.source.peek() {
+        if Some(c) == self.peek() {
             self.advance();
             true
         } else {
@@ -200,26 +514,103 @@ impl<'a> Scanner<'a> {
         }
     }
 
+    // source text of the token being scanned
+    fn lexeme(&self) -> &'a str {
+        &self.source[self.start..self.offset]
+    }
+
+    fn intern(&mut self, lexeme: Cow<'a, str>) -> Rc<str> {
+        if let Some(interned) = self.interner.get(&*lexeme) {
+            return interned.clone();
+        }
+        let interned: Rc<str> = Rc::from(&*lexeme);
+        self.interner.insert(lexeme, interned.clone());
+        interned
+    }
+
     fn finalize_token(&mut self, token_type: TokenType) -> Token {
-        let lexeme = String::from_iter(self.curr_buf.drain(..));
+        let lexeme = self.intern(Cow::Borrowed(self.lexeme()));
         Token {
             token_type,
             lexeme,
             literal: None,
-            line: self.line,
+            span: self.span(),
+            trivia: Box::default(),
+        }
+    }
+
+    // placeholder for text that doesn't produce a token
+    fn skipped(&mut self, kind: TriviaKind) -> Token {
+        self.record_trivia(kind);
+        let lexeme = self.intern(Cow::Borrowed(""));
+        Token::new(TokenType::Ignore, lexeme, None, self.span())
+    }
+
+    fn record_trivia(&mut self, kind: TriviaKind) {
+        if self.lossless {
+            let span = self.span();
+            self.trivia.push(Trivia { kind, span });
         }
     }
 
-    fn finalize_error_token(&mut self, msg: Option<&'static str>) -> Token {
-        let token_type = TokenType::SyntaxError { error_msg: msg };
-        self.finalize_token(token_type)
+    fn mark_start(&mut self) {
+        self.start = self.offset;
+        self.start_line = self.line;
+        self.start_column = self.column + 1;
+    }
+
+    // span of the token being scanned, from its first character up to the
+    // last character consumed
+    fn span(&self) -> Span {
+        Span::new(self.start, self.offset, self.start_line, self.start_column)
     }
 
-    fn error(&self, line: u32, msg: &str) {
-        self.report(line, "", msg);
+    // records a lexical error covering the current token, which is then
+    // dropped from the token stream
+    fn error(&mut self, msg: &str) -> Token {
+        self.error_at(self.span(), msg);
+        self.skipped(TriviaKind::Skipped)
     }
 
-    fn report(&self, line: u32, loc: &str, msg: &str) {
-        eprintln!("[line {}] Error {}: {}", line, loc, msg)
+    fn error_at(&mut self, span: Span, msg: &str) {
+        self.errors.push(LoxError::ScannerError(msg.into(), span));
+    }
+}
+
+impl Iterator for Scanner<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        if self.finished {
+            return None;
+        }
+        while let Some(mut token) = self.scan_token() {
+            if token.token_type != TokenType::Ignore {
+                token.trivia = std::mem::take(&mut self.trivia).into_boxed_slice();
+                return Some(token);
+            }
+        }
+        self.finished = true;
+        Some(self.eof())
+    }
+}
+
+// whether every `_` in a numeric literal sits between two digits
+fn separators_between<F>(literal: &str, is_digit: F) -> bool
+where
+    F: Fn(char) -> bool,
+{
+    let chars: Vec<char> = literal.chars().collect();
+    chars.iter().enumerate().all(|(i, c)| {
+        c != &'_'
+            || (i > 0 && is_digit(chars[i - 1]) && chars.get(i + 1).is_some_and(|&c| is_digit(c)))
+    })
+}
+
+fn without_separators(literal: &str) -> Cow<'_, str> {
+    if literal.contains('_') {
+        Cow::Owned(literal.replace('_', ""))
+    } else {
+        Cow::Borrowed(literal)
     }
 }
@@ -1,20 +1,62 @@
-use std::fmt;
+use std::{fmt, rc::Rc};
+
+/// A region of source text. `start` and `end` are byte offsets, while `line`
+/// and `column` are the 1-based position of the first character, with columns
+/// counted in characters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: u32, column: u32) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    /// Span covering both `self` and everything up to the end of `other`.
+    pub fn to(&self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end.max(self.end),
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Token {
     pub token_type: TokenType,
-    pub lexeme: String,
+    // interned by the scanner, so cloning a token doesn't copy its text
+    pub lexeme: Rc<str>,
     pub literal: Option<Literal>,
-    pub line: u32,
+    pub span: Span,
+    // whitespace and comments preceding the token, only kept by a lossless
+    // scanner
+    pub trivia: Box<[Trivia]>,
 }
 
 impl Token {
-    pub fn new(token_type: TokenType, lexeme: String, literal: Option<Literal>, line: u32) -> Self {
+    pub fn new(
+        token_type: TokenType,
+        lexeme: Rc<str>,
+        literal: Option<Literal>,
+        span: Span,
+    ) -> Self {
         Self {
             token_type,
             lexeme,
             literal,
-            line,
+            span,
+            trivia: Box::default(),
         }
     }
 }
@@ -22,21 +64,34 @@ impl Token {
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut literal_str = String::new();
-        match &self.literal {
-            Some(l) => literal_str = l.to_string(),
-            None => (),
+        if let Some(l) = &self.literal {
+            literal_str = l.to_string();
         }
 
         write!(
             f,
             "type: {} lexeme: {} literal: {}",
-            self.token_type.to_string(),
-            self.lexeme,
-            literal_str,
+            self.token_type, self.lexeme, literal_str,
         )
     }
 }
 
+/// Source text between tokens that doesn't affect the meaning of a program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    LineComment,
+    BlockComment,
+    // invalid input, reported as a lexical error
+    Skipped,
+}
+
 #[derive(Debug, Clone)]
 pub enum Literal {
     Identifer(String),
@@ -46,7 +101,11 @@ pub enum Literal {
 
 impl fmt::Display for Literal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self)
+        match self {
+            Literal::Identifer(s) => write!(f, "{}", s),
+            Literal::Str(s) => write!(f, "{}", s),
+            Literal::Number(n) => write!(f, "{}", n),
+        }
     }
 }
 
@@ -60,6 +119,7 @@ pub enum TokenType {
     Comma,
     Dot,
     Minus,
+    Percent,
     Plus,
     SemiColon,
     Slash,
@@ -74,10 +134,18 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    MinusEqual,
+    MinusMinus,
+    PlusEqual,
+    PlusPlus,
+    SlashEqual,
+    StarEqual,
 
     // Literals
     Identifer,
     String,
+    // a string segment followed by `${`
+    Interpolation,
     Number,
 
     // Keywords
@@ -100,8 +168,6 @@ pub enum TokenType {
 
     Eof,
     Ignore,
-
-    SyntaxError { error_msg: Option<&'static str> },
 }
 
 impl fmt::Display for TokenType {
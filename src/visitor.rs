@@ -0,0 +1,892 @@
+use std::rc::Rc;
+
+use crate::parser::{
+    AssignExpr, BinaryExpr, BlockStmt, CallExpr, ClassStmt, CompoundAssignExpr, Expr,
+    ExpressionStmt, FunctionStmt, GetExpr, GroupingExpr, IfStmt, InterpolationExpr, LiteralExpr,
+    LogicalExpr, PrintStmt, ReturnStmt, SetExpr, Stmt, SuperExpr, ThisExpr, UnaryExpr, UpdateExpr,
+    VarStmt, VariableExpr, WhileStmt,
+};
+
+/// Read-only traversal of the syntax tree producing an `R` for every node.
+///
+/// Each `visit_*` method defaults to the matching `walk_*` function, which
+/// visits the node's children in source order and combines their results
+/// with `aggregate_result`, so an implementation only overrides the nodes it
+/// cares about. An override can call the `walk_*` function itself to keep
+/// descending.
+pub trait Visitor<R> {
+    /// Result of a node without children, and the start of the aggregate.
+    fn default_result(&mut self) -> R;
+
+    /// Combines the result so far with the result of the next child. By
+    /// default the last child's result wins.
+    fn aggregate_result(&mut self, _aggregate: R, next: R) -> R {
+        next
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) -> R {
+        walk_stmt(self, stmt)
+    }
+
+    fn visit_block_stmt(&mut self, stmt: &BlockStmt) -> R {
+        walk_block_stmt(self, stmt)
+    }
+
+    fn visit_class_stmt(&mut self, stmt: &ClassStmt) -> R {
+        walk_class_stmt(self, stmt)
+    }
+
+    fn visit_expression_stmt(&mut self, stmt: &ExpressionStmt) -> R {
+        walk_expression_stmt(self, stmt)
+    }
+
+    fn visit_function_stmt(&mut self, stmt: &FunctionStmt) -> R {
+        walk_function_stmt(self, stmt)
+    }
+
+    fn visit_if_stmt(&mut self, stmt: &IfStmt) -> R {
+        walk_if_stmt(self, stmt)
+    }
+
+    fn visit_print_stmt(&mut self, stmt: &PrintStmt) -> R {
+        walk_print_stmt(self, stmt)
+    }
+
+    fn visit_return_stmt(&mut self, stmt: &ReturnStmt) -> R {
+        walk_return_stmt(self, stmt)
+    }
+
+    fn visit_var_stmt(&mut self, stmt: &VarStmt) -> R {
+        walk_var_stmt(self, stmt)
+    }
+
+    fn visit_while_stmt(&mut self, stmt: &WhileStmt) -> R {
+        walk_while_stmt(self, stmt)
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) -> R {
+        walk_expr(self, expr)
+    }
+
+    fn visit_assign_expr(&mut self, expr: &AssignExpr) -> R {
+        walk_assign_expr(self, expr)
+    }
+
+    fn visit_binary_expr(&mut self, expr: &BinaryExpr) -> R {
+        walk_binary_expr(self, expr)
+    }
+
+    fn visit_call_expr(&mut self, expr: &CallExpr) -> R {
+        walk_call_expr(self, expr)
+    }
+
+    fn visit_compound_assign_expr(&mut self, expr: &CompoundAssignExpr) -> R {
+        walk_compound_assign_expr(self, expr)
+    }
+
+    fn visit_get_expr(&mut self, expr: &GetExpr) -> R {
+        walk_get_expr(self, expr)
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &GroupingExpr) -> R {
+        walk_grouping_expr(self, expr)
+    }
+
+    fn visit_interpolation_expr(&mut self, expr: &InterpolationExpr) -> R {
+        walk_interpolation_expr(self, expr)
+    }
+
+    fn visit_literal_expr(&mut self, _expr: &LiteralExpr) -> R {
+        self.default_result()
+    }
+
+    fn visit_logical_expr(&mut self, expr: &LogicalExpr) -> R {
+        walk_logical_expr(self, expr)
+    }
+
+    fn visit_set_expr(&mut self, expr: &SetExpr) -> R {
+        walk_set_expr(self, expr)
+    }
+
+    fn visit_super_expr(&mut self, _expr: &SuperExpr) -> R {
+        self.default_result()
+    }
+
+    fn visit_this_expr(&mut self, _expr: &ThisExpr) -> R {
+        self.default_result()
+    }
+
+    fn visit_unary_expr(&mut self, expr: &UnaryExpr) -> R {
+        walk_unary_expr(self, expr)
+    }
+
+    fn visit_update_expr(&mut self, expr: &UpdateExpr) -> R {
+        walk_update_expr(self, expr)
+    }
+
+    fn visit_variable_expr(&mut self, _expr: &VariableExpr) -> R {
+        self.default_result()
+    }
+}
+
+pub fn walk_stmt<R, V: Visitor<R> + ?Sized>(visitor: &mut V, stmt: &Stmt) -> R {
+    match stmt {
+        Stmt::Block(s) => visitor.visit_block_stmt(s),
+        Stmt::Class(s) => visitor.visit_class_stmt(s),
+        Stmt::Expression(s) => visitor.visit_expression_stmt(s),
+        Stmt::Function(s) => visitor.visit_function_stmt(s),
+        Stmt::If(s) => visitor.visit_if_stmt(s),
+        Stmt::Print(s) => visitor.visit_print_stmt(s),
+        Stmt::Return(s) => visitor.visit_return_stmt(s),
+        Stmt::Var(s) => visitor.visit_var_stmt(s),
+        Stmt::While(s) => visitor.visit_while_stmt(s),
+    }
+}
+
+pub fn walk_block_stmt<R, V: Visitor<R> + ?Sized>(visitor: &mut V, stmt: &BlockStmt) -> R {
+    let result = visitor.default_result();
+    walk_statements(visitor, result, &stmt.statements)
+}
+
+pub fn walk_class_stmt<R, V: Visitor<R> + ?Sized>(visitor: &mut V, stmt: &ClassStmt) -> R {
+    let mut result = visitor.default_result();
+    if let Some(superclass) = &stmt.superclass {
+        let next = visitor.visit_variable_expr(superclass);
+        result = visitor.aggregate_result(result, next);
+    }
+    for method in &stmt.methods {
+        let next = visitor.visit_function_stmt(method);
+        result = visitor.aggregate_result(result, next);
+    }
+    result
+}
+
+pub fn walk_expression_stmt<R, V: Visitor<R> + ?Sized>(
+    visitor: &mut V,
+    stmt: &ExpressionStmt,
+) -> R {
+    let result = visitor.default_result();
+    walk_expressions(visitor, result, [&stmt.expression])
+}
+
+pub fn walk_function_stmt<R, V: Visitor<R> + ?Sized>(visitor: &mut V, stmt: &FunctionStmt) -> R {
+    let result = visitor.default_result();
+    walk_statements(visitor, result, stmt.body.iter())
+}
+
+pub fn walk_if_stmt<R, V: Visitor<R> + ?Sized>(visitor: &mut V, stmt: &IfStmt) -> R {
+    let result = visitor.default_result();
+    let result = walk_expressions(visitor, result, [&stmt.condition]);
+    let result = walk_statements(visitor, result, [&*stmt.then_branch]);
+    walk_statements(visitor, result, stmt.else_branch.as_deref())
+}
+
+pub fn walk_print_stmt<R, V: Visitor<R> + ?Sized>(visitor: &mut V, stmt: &PrintStmt) -> R {
+    let result = visitor.default_result();
+    walk_expressions(visitor, result, [&stmt.expression])
+}
+
+pub fn walk_return_stmt<R, V: Visitor<R> + ?Sized>(visitor: &mut V, stmt: &ReturnStmt) -> R {
+    let result = visitor.default_result();
+    walk_expressions(visitor, result, &stmt.value)
+}
+
+pub fn walk_var_stmt<R, V: Visitor<R> + ?Sized>(visitor: &mut V, stmt: &VarStmt) -> R {
+    let result = visitor.default_result();
+    walk_expressions(visitor, result, &stmt.initializer)
+}
+
+pub fn walk_while_stmt<R, V: Visitor<R> + ?Sized>(visitor: &mut V, stmt: &WhileStmt) -> R {
+    let result = visitor.default_result();
+    let result = walk_expressions(visitor, result, [&stmt.condition]);
+    walk_statements(visitor, result, [&*stmt.body])
+}
+
+pub fn walk_expr<R, V: Visitor<R> + ?Sized>(visitor: &mut V, expr: &Expr) -> R {
+    match expr {
+        Expr::Assign(e) => visitor.visit_assign_expr(e),
+        Expr::Binary(e) => visitor.visit_binary_expr(e),
+        Expr::Call(e) => visitor.visit_call_expr(e),
+        Expr::CompoundAssign(e) => visitor.visit_compound_assign_expr(e),
+        Expr::Get(e) => visitor.visit_get_expr(e),
+        Expr::Grouping(e) => visitor.visit_grouping_expr(e),
+        Expr::Interpolation(e) => visitor.visit_interpolation_expr(e),
+        Expr::Literal(e) => visitor.visit_literal_expr(e),
+        Expr::Logical(e) => visitor.visit_logical_expr(e),
+        Expr::Set(e) => visitor.visit_set_expr(e),
+        Expr::Super(e) => visitor.visit_super_expr(e),
+        Expr::This(e) => visitor.visit_this_expr(e),
+        Expr::Unary(e) => visitor.visit_unary_expr(e),
+        Expr::Update(e) => visitor.visit_update_expr(e),
+        Expr::Variable(e) => visitor.visit_variable_expr(e),
+    }
+}
+
+pub fn walk_assign_expr<R, V: Visitor<R> + ?Sized>(visitor: &mut V, expr: &AssignExpr) -> R {
+    let result = visitor.default_result();
+    walk_expressions(visitor, result, [&*expr.value])
+}
+
+pub fn walk_binary_expr<R, V: Visitor<R> + ?Sized>(visitor: &mut V, expr: &BinaryExpr) -> R {
+    let result = visitor.default_result();
+    walk_expressions(visitor, result, [&*expr.left, &*expr.right])
+}
+
+pub fn walk_call_expr<R, V: Visitor<R> + ?Sized>(visitor: &mut V, expr: &CallExpr) -> R {
+    let result = visitor.default_result();
+    let result = walk_expressions(visitor, result, [&*expr.callee]);
+    walk_expressions(visitor, result, &expr.arguments)
+}
+
+pub fn walk_compound_assign_expr<R, V: Visitor<R> + ?Sized>(
+    visitor: &mut V,
+    expr: &CompoundAssignExpr,
+) -> R {
+    let result = visitor.default_result();
+    walk_expressions(visitor, result, [&*expr.target, &*expr.value])
+}
+
+pub fn walk_get_expr<R, V: Visitor<R> + ?Sized>(visitor: &mut V, expr: &GetExpr) -> R {
+    let result = visitor.default_result();
+    walk_expressions(visitor, result, [&*expr.object])
+}
+
+pub fn walk_grouping_expr<R, V: Visitor<R> + ?Sized>(visitor: &mut V, expr: &GroupingExpr) -> R {
+    let result = visitor.default_result();
+    walk_expressions(visitor, result, [&*expr.expression])
+}
+
+pub fn walk_interpolation_expr<R, V: Visitor<R> + ?Sized>(
+    visitor: &mut V,
+    expr: &InterpolationExpr,
+) -> R {
+    let result = visitor.default_result();
+    walk_expressions(visitor, result, &expr.parts)
+}
+
+pub fn walk_logical_expr<R, V: Visitor<R> + ?Sized>(visitor: &mut V, expr: &LogicalExpr) -> R {
+    let result = visitor.default_result();
+    walk_expressions(visitor, result, [&*expr.left, &*expr.right])
+}
+
+pub fn walk_set_expr<R, V: Visitor<R> + ?Sized>(visitor: &mut V, expr: &SetExpr) -> R {
+    let result = visitor.default_result();
+    walk_expressions(visitor, result, [&*expr.object, &*expr.value])
+}
+
+pub fn walk_unary_expr<R, V: Visitor<R> + ?Sized>(visitor: &mut V, expr: &UnaryExpr) -> R {
+    let result = visitor.default_result();
+    walk_expressions(visitor, result, [&*expr.right])
+}
+
+pub fn walk_update_expr<R, V: Visitor<R> + ?Sized>(visitor: &mut V, expr: &UpdateExpr) -> R {
+    let result = visitor.default_result();
+    walk_expressions(visitor, result, [&*expr.target])
+}
+
+// visits each statement in turn, aggregating onto `result`
+fn walk_statements<'s, R, V: Visitor<R> + ?Sized>(
+    visitor: &mut V,
+    mut result: R,
+    statements: impl IntoIterator<Item = &'s Stmt>,
+) -> R {
+    for stmt in statements {
+        let next = visitor.visit_stmt(stmt);
+        result = visitor.aggregate_result(result, next);
+    }
+    result
+}
+
+// visits each expression in turn, aggregating onto `result`
+fn walk_expressions<'e, R, V: Visitor<R> + ?Sized>(
+    visitor: &mut V,
+    mut result: R,
+    expressions: impl IntoIterator<Item = &'e Expr>,
+) -> R {
+    for expr in expressions {
+        let next = visitor.visit_expr(expr);
+        result = visitor.aggregate_result(result, next);
+    }
+    result
+}
+
+/// Traversal of the syntax tree that may modify it in place, such as
+/// rewriting an expression through `visit_expr`.
+///
+/// Each `visit_*` method defaults to the matching `walk_*_mut` function,
+/// which visits the node's children in source order.
+pub trait VisitorMut {
+    fn visit_stmt(&mut self, stmt: &mut Stmt) {
+        walk_stmt_mut(self, stmt)
+    }
+
+    fn visit_block_stmt(&mut self, stmt: &mut BlockStmt) {
+        walk_block_stmt_mut(self, stmt)
+    }
+
+    fn visit_class_stmt(&mut self, stmt: &mut ClassStmt) {
+        walk_class_stmt_mut(self, stmt)
+    }
+
+    fn visit_expression_stmt(&mut self, stmt: &mut ExpressionStmt) {
+        walk_expression_stmt_mut(self, stmt)
+    }
+
+    fn visit_function_stmt(&mut self, stmt: &mut FunctionStmt) {
+        walk_function_stmt_mut(self, stmt)
+    }
+
+    fn visit_if_stmt(&mut self, stmt: &mut IfStmt) {
+        walk_if_stmt_mut(self, stmt)
+    }
+
+    fn visit_print_stmt(&mut self, stmt: &mut PrintStmt) {
+        walk_print_stmt_mut(self, stmt)
+    }
+
+    fn visit_return_stmt(&mut self, stmt: &mut ReturnStmt) {
+        walk_return_stmt_mut(self, stmt)
+    }
+
+    fn visit_var_stmt(&mut self, stmt: &mut VarStmt) {
+        walk_var_stmt_mut(self, stmt)
+    }
+
+    fn visit_while_stmt(&mut self, stmt: &mut WhileStmt) {
+        walk_while_stmt_mut(self, stmt)
+    }
+
+    fn visit_expr(&mut self, expr: &mut Expr) {
+        walk_expr_mut(self, expr)
+    }
+
+    fn visit_assign_expr(&mut self, expr: &mut AssignExpr) {
+        walk_assign_expr_mut(self, expr)
+    }
+
+    fn visit_binary_expr(&mut self, expr: &mut BinaryExpr) {
+        walk_binary_expr_mut(self, expr)
+    }
+
+    fn visit_call_expr(&mut self, expr: &mut CallExpr) {
+        walk_call_expr_mut(self, expr)
+    }
+
+    fn visit_compound_assign_expr(&mut self, expr: &mut CompoundAssignExpr) {
+        walk_compound_assign_expr_mut(self, expr)
+    }
+
+    fn visit_get_expr(&mut self, expr: &mut GetExpr) {
+        walk_get_expr_mut(self, expr)
+    }
+
+    fn visit_grouping_expr(&mut self, expr: &mut GroupingExpr) {
+        walk_grouping_expr_mut(self, expr)
+    }
+
+    fn visit_interpolation_expr(&mut self, expr: &mut InterpolationExpr) {
+        walk_interpolation_expr_mut(self, expr)
+    }
+
+    fn visit_literal_expr(&mut self, _expr: &mut LiteralExpr) {}
+
+    fn visit_logical_expr(&mut self, expr: &mut LogicalExpr) {
+        walk_logical_expr_mut(self, expr)
+    }
+
+    fn visit_set_expr(&mut self, expr: &mut SetExpr) {
+        walk_set_expr_mut(self, expr)
+    }
+
+    fn visit_super_expr(&mut self, _expr: &mut SuperExpr) {}
+
+    fn visit_this_expr(&mut self, _expr: &mut ThisExpr) {}
+
+    fn visit_unary_expr(&mut self, expr: &mut UnaryExpr) {
+        walk_unary_expr_mut(self, expr)
+    }
+
+    fn visit_update_expr(&mut self, expr: &mut UpdateExpr) {
+        walk_update_expr_mut(self, expr)
+    }
+
+    fn visit_variable_expr(&mut self, _expr: &mut VariableExpr) {}
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut Stmt) {
+    match stmt {
+        Stmt::Block(s) => visitor.visit_block_stmt(s),
+        Stmt::Class(s) => visitor.visit_class_stmt(s),
+        Stmt::Expression(s) => visitor.visit_expression_stmt(s),
+        Stmt::Function(s) => visitor.visit_function_stmt(s),
+        Stmt::If(s) => visitor.visit_if_stmt(s),
+        Stmt::Print(s) => visitor.visit_print_stmt(s),
+        Stmt::Return(s) => visitor.visit_return_stmt(s),
+        Stmt::Var(s) => visitor.visit_var_stmt(s),
+        Stmt::While(s) => visitor.visit_while_stmt(s),
+    }
+}
+
+pub fn walk_block_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut BlockStmt) {
+    for statement in &mut stmt.statements {
+        visitor.visit_stmt(statement);
+    }
+}
+
+pub fn walk_class_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut ClassStmt) {
+    if let Some(superclass) = &mut stmt.superclass {
+        visitor.visit_variable_expr(superclass);
+    }
+    for method in &mut stmt.methods {
+        visitor.visit_function_stmt(method);
+    }
+}
+
+pub fn walk_expression_stmt_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    stmt: &mut ExpressionStmt,
+) {
+    visitor.visit_expr(&mut stmt.expression);
+}
+
+// the body is shared with the functions created from it at runtime, and is
+// copied if any exist
+pub fn walk_function_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut FunctionStmt) {
+    for statement in Rc::make_mut(&mut stmt.body) {
+        visitor.visit_stmt(statement);
+    }
+}
+
+pub fn walk_if_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut IfStmt) {
+    visitor.visit_expr(&mut stmt.condition);
+    visitor.visit_stmt(&mut stmt.then_branch);
+    if let Some(else_branch) = &mut stmt.else_branch {
+        visitor.visit_stmt(else_branch);
+    }
+}
+
+pub fn walk_print_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut PrintStmt) {
+    visitor.visit_expr(&mut stmt.expression);
+}
+
+pub fn walk_return_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut ReturnStmt) {
+    if let Some(value) = &mut stmt.value {
+        visitor.visit_expr(value);
+    }
+}
+
+pub fn walk_var_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut VarStmt) {
+    if let Some(initializer) = &mut stmt.initializer {
+        visitor.visit_expr(initializer);
+    }
+}
+
+pub fn walk_while_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut WhileStmt) {
+    visitor.visit_expr(&mut stmt.condition);
+    visitor.visit_stmt(&mut stmt.body);
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut Expr) {
+    match expr {
+        Expr::Assign(e) => visitor.visit_assign_expr(e),
+        Expr::Binary(e) => visitor.visit_binary_expr(e),
+        Expr::Call(e) => visitor.visit_call_expr(e),
+        Expr::CompoundAssign(e) => visitor.visit_compound_assign_expr(e),
+        Expr::Get(e) => visitor.visit_get_expr(e),
+        Expr::Grouping(e) => visitor.visit_grouping_expr(e),
+        Expr::Interpolation(e) => visitor.visit_interpolation_expr(e),
+        Expr::Literal(e) => visitor.visit_literal_expr(e),
+        Expr::Logical(e) => visitor.visit_logical_expr(e),
+        Expr::Set(e) => visitor.visit_set_expr(e),
+        Expr::Super(e) => visitor.visit_super_expr(e),
+        Expr::This(e) => visitor.visit_this_expr(e),
+        Expr::Unary(e) => visitor.visit_unary_expr(e),
+        Expr::Update(e) => visitor.visit_update_expr(e),
+        Expr::Variable(e) => visitor.visit_variable_expr(e),
+    }
+}
+
+pub fn walk_assign_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut AssignExpr) {
+    visitor.visit_expr(&mut expr.value);
+}
+
+pub fn walk_binary_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut BinaryExpr) {
+    visitor.visit_expr(&mut expr.left);
+    visitor.visit_expr(&mut expr.right);
+}
+
+pub fn walk_call_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut CallExpr) {
+    visitor.visit_expr(&mut expr.callee);
+    for argument in &mut expr.arguments {
+        visitor.visit_expr(argument);
+    }
+}
+
+pub fn walk_compound_assign_expr_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    expr: &mut CompoundAssignExpr,
+) {
+    visitor.visit_expr(&mut expr.target);
+    visitor.visit_expr(&mut expr.value);
+}
+
+pub fn walk_get_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut GetExpr) {
+    visitor.visit_expr(&mut expr.object);
+}
+
+pub fn walk_grouping_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut GroupingExpr) {
+    visitor.visit_expr(&mut expr.expression);
+}
+
+pub fn walk_interpolation_expr_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    expr: &mut InterpolationExpr,
+) {
+    for part in &mut expr.parts {
+        visitor.visit_expr(part);
+    }
+}
+
+pub fn walk_logical_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut LogicalExpr) {
+    visitor.visit_expr(&mut expr.left);
+    visitor.visit_expr(&mut expr.right);
+}
+
+pub fn walk_set_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut SetExpr) {
+    visitor.visit_expr(&mut expr.object);
+    visitor.visit_expr(&mut expr.value);
+}
+
+pub fn walk_unary_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut UnaryExpr) {
+    visitor.visit_expr(&mut expr.right);
+}
+
+pub fn walk_update_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expr: &mut UpdateExpr) {
+    visitor.visit_expr(&mut expr.target);
+}
+
+/// Rebuilds the syntax tree by value, e.g. for an optimizer replacing
+/// `1 + 2` with `3`.
+///
+/// Each `fold_*` method defaults to the matching `fold_*` function, which
+/// folds the node's children in source order and rebuilds the node from
+/// them. Nodes change kind by overriding `fold_stmt` or `fold_expr`.
+pub trait Folder {
+    fn fold_stmt(&mut self, stmt: Stmt) -> Stmt {
+        fold_stmt(self, stmt)
+    }
+
+    fn fold_block_stmt(&mut self, stmt: BlockStmt) -> BlockStmt {
+        fold_block_stmt(self, stmt)
+    }
+
+    fn fold_class_stmt(&mut self, stmt: ClassStmt) -> ClassStmt {
+        fold_class_stmt(self, stmt)
+    }
+
+    fn fold_expression_stmt(&mut self, stmt: ExpressionStmt) -> ExpressionStmt {
+        fold_expression_stmt(self, stmt)
+    }
+
+    fn fold_function_stmt(&mut self, stmt: FunctionStmt) -> FunctionStmt {
+        fold_function_stmt(self, stmt)
+    }
+
+    fn fold_if_stmt(&mut self, stmt: IfStmt) -> IfStmt {
+        fold_if_stmt(self, stmt)
+    }
+
+    fn fold_print_stmt(&mut self, stmt: PrintStmt) -> PrintStmt {
+        fold_print_stmt(self, stmt)
+    }
+
+    fn fold_return_stmt(&mut self, stmt: ReturnStmt) -> ReturnStmt {
+        fold_return_stmt(self, stmt)
+    }
+
+    fn fold_var_stmt(&mut self, stmt: VarStmt) -> VarStmt {
+        fold_var_stmt(self, stmt)
+    }
+
+    fn fold_while_stmt(&mut self, stmt: WhileStmt) -> WhileStmt {
+        fold_while_stmt(self, stmt)
+    }
+
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        fold_expr(self, expr)
+    }
+
+    fn fold_assign_expr(&mut self, expr: AssignExpr) -> AssignExpr {
+        fold_assign_expr(self, expr)
+    }
+
+    fn fold_binary_expr(&mut self, expr: BinaryExpr) -> BinaryExpr {
+        fold_binary_expr(self, expr)
+    }
+
+    fn fold_call_expr(&mut self, expr: CallExpr) -> CallExpr {
+        fold_call_expr(self, expr)
+    }
+
+    fn fold_compound_assign_expr(&mut self, expr: CompoundAssignExpr) -> CompoundAssignExpr {
+        fold_compound_assign_expr(self, expr)
+    }
+
+    fn fold_get_expr(&mut self, expr: GetExpr) -> GetExpr {
+        fold_get_expr(self, expr)
+    }
+
+    fn fold_grouping_expr(&mut self, expr: GroupingExpr) -> GroupingExpr {
+        fold_grouping_expr(self, expr)
+    }
+
+    fn fold_interpolation_expr(&mut self, expr: InterpolationExpr) -> InterpolationExpr {
+        fold_interpolation_expr(self, expr)
+    }
+
+    fn fold_literal_expr(&mut self, expr: LiteralExpr) -> LiteralExpr {
+        expr
+    }
+
+    fn fold_logical_expr(&mut self, expr: LogicalExpr) -> LogicalExpr {
+        fold_logical_expr(self, expr)
+    }
+
+    fn fold_set_expr(&mut self, expr: SetExpr) -> SetExpr {
+        fold_set_expr(self, expr)
+    }
+
+    fn fold_super_expr(&mut self, expr: SuperExpr) -> SuperExpr {
+        expr
+    }
+
+    fn fold_this_expr(&mut self, expr: ThisExpr) -> ThisExpr {
+        expr
+    }
+
+    fn fold_unary_expr(&mut self, expr: UnaryExpr) -> UnaryExpr {
+        fold_unary_expr(self, expr)
+    }
+
+    fn fold_update_expr(&mut self, expr: UpdateExpr) -> UpdateExpr {
+        fold_update_expr(self, expr)
+    }
+
+    fn fold_variable_expr(&mut self, expr: VariableExpr) -> VariableExpr {
+        expr
+    }
+}
+
+pub fn fold_stmt<F: Folder + ?Sized>(folder: &mut F, stmt: Stmt) -> Stmt {
+    match stmt {
+        Stmt::Block(s) => Stmt::Block(folder.fold_block_stmt(s)),
+        Stmt::Class(s) => Stmt::Class(folder.fold_class_stmt(s)),
+        Stmt::Expression(s) => Stmt::Expression(folder.fold_expression_stmt(s)),
+        Stmt::Function(s) => Stmt::Function(folder.fold_function_stmt(s)),
+        Stmt::If(s) => Stmt::If(folder.fold_if_stmt(s)),
+        Stmt::Print(s) => Stmt::Print(folder.fold_print_stmt(s)),
+        Stmt::Return(s) => Stmt::Return(folder.fold_return_stmt(s)),
+        Stmt::Var(s) => Stmt::Var(folder.fold_var_stmt(s)),
+        Stmt::While(s) => Stmt::While(folder.fold_while_stmt(s)),
+    }
+}
+
+pub fn fold_block_stmt<F: Folder + ?Sized>(folder: &mut F, stmt: BlockStmt) -> BlockStmt {
+    BlockStmt {
+        statements: fold_statements(folder, stmt.statements),
+        ..stmt
+    }
+}
+
+pub fn fold_class_stmt<F: Folder + ?Sized>(folder: &mut F, stmt: ClassStmt) -> ClassStmt {
+    ClassStmt {
+        superclass: stmt.superclass.map(|s| folder.fold_variable_expr(s)),
+        methods: stmt
+            .methods
+            .into_iter()
+            .map(|m| folder.fold_function_stmt(m))
+            .collect(),
+        ..stmt
+    }
+}
+
+pub fn fold_expression_stmt<F: Folder + ?Sized>(
+    folder: &mut F,
+    stmt: ExpressionStmt,
+) -> ExpressionStmt {
+    ExpressionStmt {
+        expression: folder.fold_expr(stmt.expression),
+        ..stmt
+    }
+}
+
+// the body is shared with the functions created from it at runtime, and is
+// copied if any exist
+pub fn fold_function_stmt<F: Folder + ?Sized>(folder: &mut F, stmt: FunctionStmt) -> FunctionStmt {
+    let body = Rc::try_unwrap(stmt.body).unwrap_or_else(|body| (*body).clone());
+    FunctionStmt {
+        body: Rc::new(fold_statements(folder, body)),
+        ..stmt
+    }
+}
+
+pub fn fold_if_stmt<F: Folder + ?Sized>(folder: &mut F, stmt: IfStmt) -> IfStmt {
+    IfStmt {
+        condition: folder.fold_expr(stmt.condition),
+        then_branch: Box::new(folder.fold_stmt(*stmt.then_branch)),
+        else_branch: stmt.else_branch.map(|s| Box::new(folder.fold_stmt(*s))),
+        ..stmt
+    }
+}
+
+pub fn fold_print_stmt<F: Folder + ?Sized>(folder: &mut F, stmt: PrintStmt) -> PrintStmt {
+    PrintStmt {
+        expression: folder.fold_expr(stmt.expression),
+        ..stmt
+    }
+}
+
+pub fn fold_return_stmt<F: Folder + ?Sized>(folder: &mut F, stmt: ReturnStmt) -> ReturnStmt {
+    ReturnStmt {
+        value: stmt.value.map(|e| folder.fold_expr(e)),
+        ..stmt
+    }
+}
+
+pub fn fold_var_stmt<F: Folder + ?Sized>(folder: &mut F, stmt: VarStmt) -> VarStmt {
+    VarStmt {
+        initializer: stmt.initializer.map(|e| folder.fold_expr(e)),
+        ..stmt
+    }
+}
+
+pub fn fold_while_stmt<F: Folder + ?Sized>(folder: &mut F, stmt: WhileStmt) -> WhileStmt {
+    WhileStmt {
+        condition: folder.fold_expr(stmt.condition),
+        body: Box::new(folder.fold_stmt(*stmt.body)),
+        ..stmt
+    }
+}
+
+pub fn fold_expr<F: Folder + ?Sized>(folder: &mut F, expr: Expr) -> Expr {
+    match expr {
+        Expr::Assign(e) => Expr::Assign(folder.fold_assign_expr(e)),
+        Expr::Binary(e) => Expr::Binary(folder.fold_binary_expr(e)),
+        Expr::Call(e) => Expr::Call(folder.fold_call_expr(e)),
+        Expr::CompoundAssign(e) => Expr::CompoundAssign(folder.fold_compound_assign_expr(e)),
+        Expr::Get(e) => Expr::Get(folder.fold_get_expr(e)),
+        Expr::Grouping(e) => Expr::Grouping(folder.fold_grouping_expr(e)),
+        Expr::Interpolation(e) => Expr::Interpolation(folder.fold_interpolation_expr(e)),
+        Expr::Literal(e) => Expr::Literal(folder.fold_literal_expr(e)),
+        Expr::Logical(e) => Expr::Logical(folder.fold_logical_expr(e)),
+        Expr::Set(e) => Expr::Set(folder.fold_set_expr(e)),
+        Expr::Super(e) => Expr::Super(folder.fold_super_expr(e)),
+        Expr::This(e) => Expr::This(folder.fold_this_expr(e)),
+        Expr::Unary(e) => Expr::Unary(folder.fold_unary_expr(e)),
+        Expr::Update(e) => Expr::Update(folder.fold_update_expr(e)),
+        Expr::Variable(e) => Expr::Variable(folder.fold_variable_expr(e)),
+    }
+}
+
+pub fn fold_assign_expr<F: Folder + ?Sized>(folder: &mut F, expr: AssignExpr) -> AssignExpr {
+    AssignExpr {
+        value: Box::new(folder.fold_expr(*expr.value)),
+        ..expr
+    }
+}
+
+pub fn fold_binary_expr<F: Folder + ?Sized>(folder: &mut F, expr: BinaryExpr) -> BinaryExpr {
+    BinaryExpr {
+        left: Box::new(folder.fold_expr(*expr.left)),
+        right: Box::new(folder.fold_expr(*expr.right)),
+        ..expr
+    }
+}
+
+pub fn fold_call_expr<F: Folder + ?Sized>(folder: &mut F, expr: CallExpr) -> CallExpr {
+    CallExpr {
+        callee: Box::new(folder.fold_expr(*expr.callee)),
+        arguments: expr
+            .arguments
+            .into_iter()
+            .map(|e| folder.fold_expr(e))
+            .collect(),
+        ..expr
+    }
+}
+
+pub fn fold_compound_assign_expr<F: Folder + ?Sized>(
+    folder: &mut F,
+    expr: CompoundAssignExpr,
+) -> CompoundAssignExpr {
+    CompoundAssignExpr {
+        target: Box::new(folder.fold_expr(*expr.target)),
+        value: Box::new(folder.fold_expr(*expr.value)),
+        ..expr
+    }
+}
+
+pub fn fold_get_expr<F: Folder + ?Sized>(folder: &mut F, expr: GetExpr) -> GetExpr {
+    GetExpr {
+        object: Box::new(folder.fold_expr(*expr.object)),
+        ..expr
+    }
+}
+
+pub fn fold_grouping_expr<F: Folder + ?Sized>(folder: &mut F, expr: GroupingExpr) -> GroupingExpr {
+    GroupingExpr {
+        expression: Box::new(folder.fold_expr(*expr.expression)),
+        ..expr
+    }
+}
+
+pub fn fold_interpolation_expr<F: Folder + ?Sized>(
+    folder: &mut F,
+    expr: InterpolationExpr,
+) -> InterpolationExpr {
+    InterpolationExpr {
+        parts: expr
+            .parts
+            .into_iter()
+            .map(|e| folder.fold_expr(e))
+            .collect(),
+        ..expr
+    }
+}
+
+pub fn fold_logical_expr<F: Folder + ?Sized>(folder: &mut F, expr: LogicalExpr) -> LogicalExpr {
+    LogicalExpr {
+        left: Box::new(folder.fold_expr(*expr.left)),
+        right: Box::new(folder.fold_expr(*expr.right)),
+        ..expr
+    }
+}
+
+pub fn fold_set_expr<F: Folder + ?Sized>(folder: &mut F, expr: SetExpr) -> SetExpr {
+    SetExpr {
+        object: Box::new(folder.fold_expr(*expr.object)),
+        value: Box::new(folder.fold_expr(*expr.value)),
+        ..expr
+    }
+}
+
+pub fn fold_unary_expr<F: Folder + ?Sized>(folder: &mut F, expr: UnaryExpr) -> UnaryExpr {
+    UnaryExpr {
+        right: Box::new(folder.fold_expr(*expr.right)),
+        ..expr
+    }
+}
+
+pub fn fold_update_expr<F: Folder + ?Sized>(folder: &mut F, expr: UpdateExpr) -> UpdateExpr {
+    UpdateExpr {
+        target: Box::new(folder.fold_expr(*expr.target)),
+        ..expr
+    }
+}
+
+fn fold_statements<F: Folder + ?Sized>(folder: &mut F, statements: Vec<Stmt>) -> Vec<Stmt> {
+    statements
+        .into_iter()
+        .map(|s| folder.fold_stmt(s))
+        .collect()
+}
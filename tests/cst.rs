@@ -0,0 +1,126 @@
+use std::fs;
+
+use lox_rs::cst::SyntaxTree;
+
+// pieces of valid and invalid Lox, glued together at random
+const FRAGMENTS: &[&str] = &[
+    "var",
+    "fun",
+    "class",
+    "if",
+    "else",
+    "for",
+    "while",
+    "return",
+    "print",
+    "this",
+    "super",
+    "and",
+    "or",
+    "nil",
+    "true",
+    "false",
+    "x",
+    "café",
+    "_a1",
+    "0",
+    "1.5",
+    "0x1F",
+    "0b10",
+    "1_000",
+    "1e3",
+    "0x",
+    "1__0",
+    "\"s\"",
+    "\"a${b}c\"",
+    "\"${",
+    "}\"",
+    "\"\\n\\u{41}\"",
+    "\"\\q\"",
+    "\"open",
+    "\"\\",
+    "// line",
+    "/* block */",
+    "/* /* nested */ */",
+    "/* open",
+    "(",
+    ")",
+    "{",
+    "}",
+    ",",
+    ".",
+    ";",
+    "+",
+    "-",
+    "*",
+    "/",
+    "%",
+    "!",
+    "=",
+    "<",
+    ">",
+    "==",
+    "!=",
+    "<=",
+    ">=",
+    "++",
+    "--",
+    "+=",
+    "-=",
+    "*=",
+    "/=",
+    "@",
+    "#",
+    "\\",
+    "$",
+    " ",
+    "  ",
+    "\t",
+    "\n",
+    "\r\n",
+    "\n\n",
+];
+
+fn assert_round_trip(source: &str) {
+    assert_eq!(SyntaxTree::parse(source).to_string(), source);
+}
+
+#[test]
+fn examples_round_trip() {
+    for entry in fs::read_dir("examples").unwrap() {
+        let path = entry.unwrap().path();
+        assert_round_trip(&fs::read_to_string(path).unwrap());
+    }
+}
+
+#[test]
+fn random_input_round_trips() {
+    let mut seed: u64 = 0x5eed;
+    let mut random = move |n: usize| {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 33) as usize % n
+    };
+    for _ in 0..2000 {
+        let length = random(40);
+        let source: String = (0..length)
+            .map(|_| FRAGMENTS[random(FRAGMENTS.len())])
+            .collect();
+        assert_round_trip(&source);
+    }
+}
+
+#[test]
+fn empty_and_trivia_only_input_round_trips() {
+    for source in [
+        "",
+        " ",
+        "\n",
+        "// only a comment",
+        "/* unterminated",
+        "\"unterminated",
+    ] {
+        assert_round_trip(source);
+    }
+}
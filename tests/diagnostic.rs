@@ -0,0 +1,112 @@
+use std::{
+    env, fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+// runs the binary with `args`, feeding it `stdin`, and returns its exit code,
+// stdout and stderr
+fn lox(args: &[&str], stdin: &str) -> (i32, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lox-rs"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+// writes `source` to a fresh file in the temp directory
+fn script(name: &str, source: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("lox-rs-{}-{}.lox", name, std::process::id()));
+    fs::write(&path, source).unwrap();
+    path
+}
+
+#[test]
+fn prompt_points_errors_at_the_line_they_came_from() {
+    let (_, stdout, _) = lox(&[], "fun f() { return 1 + nil; }\nf();\nprint 1 +;\n");
+    let expected = "\
+> > error[E0004]: Operands must be two numbers or two strings.
+ --> <stdin>:1:20
+  |
+1 | fun f() { return 1 + nil; }
+  |                    ^
+
+> error[E0002]: Expect expression.
+ --> <stdin>:3:10
+  |
+3 | print 1 +;
+  |          ^
+
+> ";
+    assert_eq!(stdout, expected);
+}
+
+#[test]
+fn io_errors_name_the_file() {
+    let (code, _, stderr) = lox(&["fmt", "missing-a.lox", "missing-b.lox"], "");
+    assert_eq!(code, 74);
+    assert!(stderr.contains(" --> missing-a.lox\n"), "{}", stderr);
+    assert!(stderr.contains(" --> missing-b.lox\n"), "{}", stderr);
+}
+
+#[test]
+fn fmt_check_reports_unformatted_files_as_json() {
+    let path = script("fmt_check_json", "var a=1;\n");
+    let file = path.to_str().unwrap();
+    let (code, stdout, stderr) = lox(&["fmt", "--check", "--error-format=json", file], "");
+    fs::remove_file(&path).unwrap();
+    assert_eq!(code, 1);
+    assert_eq!(stdout, "");
+    assert_eq!(
+        stderr,
+        format!(
+            "{{\"file\":\"{}\",\"span\":null,\"severity\":\"warning\",\"code\":null,\"message\":\"Would reformat.\"}}\n",
+            file
+        )
+    );
+}
+
+#[test]
+fn json_errors_escape_the_file_name() {
+    let path = script("json-\"quoted\\name\u{1}", "print 1 +;\nvar x = @;\n");
+    let file = path.to_str().unwrap();
+    let (code, _, stderr) = lox(&["--error-format=json", file], "");
+    fs::remove_file(&path).unwrap();
+    assert_eq!(code, 65);
+
+    let records: Vec<serde_json::Value> = stderr
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let expected = [
+        ((19, 20, 2, 9), "E0001", "Unexpected character."),
+        ((9, 10, 1, 10), "E0002", "Expect expression."),
+        ((20, 21, 2, 10), "E0002", "Expect expression."),
+    ];
+    assert_eq!(records.len(), expected.len(), "{}", stderr);
+    for (record, ((start, end, line, column), code, message)) in records.iter().zip(expected) {
+        assert_eq!(record["file"], file);
+        assert_eq!(
+            record["span"],
+            serde_json::json!({"start": start, "end": end, "line": line, "column": column})
+        );
+        assert_eq!(record["severity"], "error");
+        assert_eq!(record["code"], code);
+        assert_eq!(record["message"], message);
+    }
+}
@@ -0,0 +1,102 @@
+use std::{fs, path::PathBuf};
+
+use lox_rs::{
+    ast_printer::{AstFormat, AstPrinter},
+    formatter,
+    parser::Parser,
+    scanner::Scanner,
+    token::TriviaKind,
+};
+
+const FIXTURES: &str = "tests/fixtures/fmt";
+
+fn lox_files(dir: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "lox"))
+        .collect();
+    files.sort();
+    files
+}
+
+// every file the invariants are checked on
+fn sources() -> Vec<(PathBuf, String)> {
+    [
+        format!("{}/input", FIXTURES),
+        format!("{}/expected", FIXTURES),
+        "examples".to_string(),
+    ]
+    .iter()
+    .flat_map(|dir| lox_files(dir))
+    .map(|path| {
+        let source = fs::read_to_string(&path).unwrap();
+        (path, source)
+    })
+    .collect()
+}
+
+fn format(source: &str) -> String {
+    match formatter::format(source) {
+        Ok(formatted) => formatted,
+        Err(errors) => panic!("{} errors formatting\n{}", errors.len(), source),
+    }
+}
+
+fn ast(source: &str) -> String {
+    let statements = Parser::new(Scanner::new(source)).parse().ok().unwrap();
+    AstPrinter::new(AstFormat::SExpr).print(&statements)
+}
+
+fn comments(source: &str) -> Vec<String> {
+    Scanner::lossless(source)
+        .flat_map(|token| token.trivia.into_vec())
+        .filter(|trivia| trivia.kind != TriviaKind::Whitespace)
+        .map(|trivia| source[trivia.span.start..trivia.span.end].to_string())
+        .collect()
+}
+
+#[test]
+fn matches_expected_output() {
+    for input in lox_files(&format!("{}/input", FIXTURES)) {
+        let expected = input.to_str().unwrap().replace("/input/", "/expected/");
+        let expected = fs::read_to_string(&expected).unwrap();
+        let source = fs::read_to_string(&input).unwrap();
+        assert_eq!(format(&source), expected, "{}", input.display());
+    }
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    for (path, source) in sources() {
+        let formatted = format(&source);
+        assert_eq!(format(&formatted), formatted, "{}", path.display());
+    }
+}
+
+#[test]
+fn formatting_keeps_the_ast() {
+    for (path, source) in sources() {
+        let formatted = format(&source);
+        assert_eq!(ast(&formatted), ast(&source), "{}", path.display());
+    }
+}
+
+#[test]
+fn formatting_keeps_comments() {
+    for (path, source) in sources() {
+        let formatted = format(&source);
+        assert_eq!(
+            comments(&formatted),
+            comments(&source),
+            "{}",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn invalid_source_is_not_formatted() {
+    assert!(formatter::format("print (1;").is_err());
+    assert!(formatter::format("print \"open").is_err());
+}
@@ -0,0 +1,242 @@
+use std::{env, fs, process::Command};
+
+// runs `source` as a script, returning its exit code and what it printed
+fn run(name: &str, source: &str) -> (i32, String) {
+    let path = env::temp_dir().join(format!("lox-rs-{}-{}.lox", name, std::process::id()));
+    fs::write(&path, source).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_lox-rs"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+fn assert_prints(name: &str, source: &str, expected: &[&str]) {
+    let (code, stdout) = run(name, source);
+    assert_eq!(code, 0, "{} exited with {}", name, code);
+    assert_eq!(stdout.lines().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn remainder() {
+    assert_prints(
+        "remainder",
+        "print 7 % 3; print -7 % 3; print 5.5 % 2; print 1 + 7 % 4 * 2;",
+        &["1", "-1", "1.5", "7"],
+    );
+}
+
+#[test]
+fn compound_assignment() {
+    assert_prints(
+        "compound_assignment",
+        r#"
+        var x = 10;
+        print x += 5;
+        print x -= 3;
+        print x *= 2;
+        print x /= 8;
+        print x;
+        var s = "a";
+        s += "b";
+        print s;
+        var a = 1;
+        var b = 2;
+        a += b += 3;
+        print a;
+        print b;
+        "#,
+        &["15", "12", "24", "3", "3", "ab", "6", "5"],
+    );
+}
+
+#[test]
+fn compound_assignment_to_property() {
+    assert_prints(
+        "compound_assignment_to_property",
+        r#"
+        class Box {}
+        var box = Box();
+        box.n = 2;
+        print box.n += 3;
+        print box.n *= 4;
+        print box.n -= 1;
+        print box.n /= 2;
+        print box.n;
+        "#,
+        &["5", "20", "19", "9.5", "9.5"],
+    );
+}
+
+#[test]
+fn increment_and_decrement() {
+    assert_prints(
+        "increment_and_decrement",
+        r#"
+        var i = 1;
+        print i++;
+        print i;
+        print ++i;
+        print i--;
+        print --i;
+        print i;
+        {
+            var local = 5;
+            local++;
+            --local;
+            print local++;
+            print local;
+        }
+        "#,
+        &["1", "2", "3", "3", "1", "1", "5", "6"],
+    );
+}
+
+#[test]
+fn postfix_yields_the_old_value_exactly() {
+    assert_prints(
+        "postfix_yields_the_old_value_exactly",
+        "var x = 0.1; print x--; print x++; var y = 0.1; print y++;",
+        &["0.1", "-0.9", "0.1"],
+    );
+}
+
+#[test]
+fn increment_and_decrement_property() {
+    assert_prints(
+        "increment_and_decrement_property",
+        r#"
+        class Counter { init() { this.count = 0; } }
+        var counter = Counter();
+        print counter.count++;
+        print ++counter.count;
+        print counter.count--;
+        print --counter.count;
+        print counter.count;
+        "#,
+        &["0", "2", "2", "0", "0"],
+    );
+}
+
+#[test]
+fn update_evaluates_receiver_once() {
+    assert_prints(
+        "update_evaluates_receiver_once",
+        r#"
+        class Box { init() { this.n = 1; } }
+        var box = Box();
+        var calls = 0;
+        fun get() {
+            calls = calls + 1;
+            return box;
+        }
+        print get().n += 1;
+        print calls;
+        print get().n++;
+        print calls;
+        print ++get().n;
+        print calls;
+        print box.n;
+        "#,
+        &["2", "1", "2", "2", "4", "3", "4"],
+    );
+}
+
+#[test]
+fn decrement_next_to_minus() {
+    assert_prints(
+        "decrement_next_to_minus",
+        r#"
+        var a = 5;
+        print a - -1;
+        print - -a;
+        print a---1;
+        print a;
+        print -a--;
+        print a;
+        "#,
+        &["6", "5", "4", "4", "-4", "3"],
+    );
+}
+
+#[test]
+fn invalid_update_targets() {
+    for (name, source) in [
+        ("increment_literal", "1++;"),
+        ("decrement_grouping", "var a = 1; --(a);"),
+        ("compound_assign_call", "fun f() {} f() += 1;"),
+        ("double_decrement", "print 1--1;"),
+    ] {
+        let (code, stdout) = run(name, source);
+        assert_eq!(code, 65, "{}", name);
+        assert_eq!(stdout, "", "{}", name);
+    }
+}
+
+#[test]
+fn string_interpolation() {
+    assert_prints(
+        "string_interpolation",
+        r#"
+        var who = "you";
+        var n = 3;
+        print "hi ${who}!";
+        print "${n}";
+        print "${n + 1} and ${"nested ${who}"} ${nil}${true}";
+        print "a\${b}";
+        "#,
+        &["hi you!", "3", "4 and nested you niltrue", "a${b}"],
+    );
+}
+
+#[test]
+fn canonically_equivalent_names_are_the_same_variable() {
+    assert_prints(
+        "canonically_equivalent_names",
+        "var caf\u{e9} = 1; cafe\u{301} = cafe\u{301} + 1; print caf\u{e9};",
+        &["2"],
+    );
+}
+
+#[test]
+fn usage_error_exits_with_ex_usage() {
+    for args in [
+        &["a.lox", "b.lox"][..],
+        &["--unknown"],
+        &["--check", "a.lox"],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_lox-rs"))
+            .args(args)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(64), "{:?}", args);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("Usage:"), "{:?}: {}", args, stderr);
+    }
+}
+
+#[test]
+fn deep_recursion() {
+    assert_prints(
+        "deep_recursion",
+        r#"
+        fun f(n) { if (n == 0) return 0; return 1 + f(n - 1); }
+        print f(5000);
+        "#,
+        &["5000"],
+    );
+}
+
+#[test]
+fn unbounded_recursion_is_a_runtime_error() {
+    let (code, stdout) = run(
+        "unbounded_recursion",
+        "fun f(n) { return f(n + 1); } print f(0);",
+    );
+    assert_eq!(code, 70);
+    assert_eq!(stdout, "");
+}
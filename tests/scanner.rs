@@ -0,0 +1,308 @@
+use lox_rs::{
+    scanner::Scanner,
+    token::{Literal, Span, Token, TokenType},
+};
+
+fn tokens(source: &str) -> Vec<Token> {
+    let (tokens, errors) = Scanner::new(source).scan_tokens();
+    assert!(errors.is_empty(), "errors scanning {:?}", source);
+    tokens
+}
+
+// each lexical error's message with the span it points at
+fn errors(source: &str) -> Vec<(String, Span)> {
+    let (_, errors) = Scanner::new(source).scan_tokens();
+    errors
+        .iter()
+        .map(|e| (e.message(), e.span().unwrap()))
+        .collect()
+}
+
+fn number(source: &str) -> f64 {
+    match tokens(source).as_slice() {
+        [Token {
+            token_type: TokenType::Number,
+            literal: Some(Literal::Number(n)),
+            ..
+        }, eof]
+            if eof.token_type == TokenType::Eof =>
+        {
+            *n
+        }
+        tokens => panic!("{:?} scanned as {:?}", source, tokens),
+    }
+}
+
+#[test]
+fn number_literals() {
+    for (source, value) in [
+        ("0", 0.0),
+        ("1.5", 1.5),
+        ("0xFF", 255.0),
+        ("0Xff", 255.0),
+        ("0b1010", 10.0),
+        ("0B1", 1.0),
+        ("1e-9", 1e-9),
+        ("6.02e23", 6.02e23),
+        ("1.5E+3", 1500.0),
+        ("1_000_000", 1_000_000.0),
+        ("0xFF_FF", 65535.0),
+        ("0b1_0", 2.0),
+        ("1_0.2_5e1_0", 10.25e10),
+        ("0xFFFFFFFFFFFFFFFF", u64::MAX as f64),
+    ] {
+        assert_eq!(number(source), value, "{}", source);
+    }
+}
+
+#[test]
+fn invalid_number_literals() {
+    for (source, message, end) in [
+        ("0x", "Expect hexadecimal digits after '0x'.", 2),
+        ("0b", "Expect binary digits after '0b'.", 2),
+        ("0x_", "Digit separators must be placed between digits.", 3),
+        ("0b102", "Invalid digit '2' in binary literal.", 5),
+        ("0xFG", "Invalid digit 'G' in hexadecimal literal.", 4),
+        ("1e", "Expect digits in number exponent.", 2),
+        ("1e+", "Expect digits in number exponent.", 3),
+        ("1E_1", "Expect digits in number exponent.", 2),
+        ("1__0", "Digit separators must be placed between digits.", 4),
+        ("1_", "Digit separators must be placed between digits.", 2),
+        ("1_.5", "Digit separators must be placed between digits.", 4),
+        ("0x_1", "Digit separators must be placed between digits.", 4),
+        ("0b1_", "Digit separators must be placed between digits.", 4),
+        (
+            "0x1_0000_0000_0000_0000",
+            "Number literal is too large.",
+            23,
+        ),
+    ] {
+        assert_eq!(
+            errors(source),
+            [(message.to_string(), Span::new(0, end, 1, 1))],
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn invalid_number_is_left_out() {
+    let tokens: Vec<TokenType> = Scanner::new("print 0x + 1;")
+        .map(|token| token.token_type)
+        .collect();
+    assert_eq!(
+        tokens,
+        [
+            TokenType::Print,
+            TokenType::Plus,
+            TokenType::Number,
+            TokenType::SemiColon,
+            TokenType::Eof
+        ]
+    );
+}
+
+fn string(source: &str) -> String {
+    match tokens(source).as_slice() {
+        [Token {
+            token_type: TokenType::String,
+            literal: Some(Literal::Str(s)),
+            ..
+        }, eof]
+            if eof.token_type == TokenType::Eof =>
+        {
+            s.clone()
+        }
+        tokens => panic!("{:?} scanned as {:?}", source, tokens),
+    }
+}
+
+#[test]
+fn escape_sequences() {
+    for (source, value) in [
+        (r#""a\nb""#, "a\nb"),
+        (r#""a\tb""#, "a\tb"),
+        (r#""a\rb""#, "a\rb"),
+        (r#""a\\b""#, "a\\b"),
+        (r#""a\"b""#, "a\"b"),
+        (r#""a\${b}""#, "a${b}"),
+        (r#""\u{41}""#, "A"),
+        (r#""\u{e9}""#, "é"),
+        (r#""\u{1F600}""#, "😀"),
+        (r#""\u{00000A}""#, "\n"),
+    ] {
+        assert_eq!(string(source), value, "{}", source);
+    }
+}
+
+#[test]
+fn invalid_escape_sequences() {
+    for (source, message, start, end) in [
+        (r#""\q""#, "Invalid escape sequence.", 1, 3),
+        (r#""ab\0""#, "Invalid escape sequence.", 3, 5),
+        (
+            r#""\u41""#,
+            "Expect '{' after '\\u' in unicode escape.",
+            1,
+            3,
+        ),
+        (
+            r#""\u{41""#,
+            "Expect '}' after unicode escape digits.",
+            1,
+            6,
+        ),
+        (
+            r#""\u{4G}""#,
+            "Expect '}' after unicode escape digits.",
+            1,
+            5,
+        ),
+        (
+            r#""\u{}""#,
+            "Unicode escape must have between 1 and 6 hex digits.",
+            1,
+            5,
+        ),
+        (
+            r#""\u{1234567}""#,
+            "Unicode escape must have between 1 and 6 hex digits.",
+            1,
+            12,
+        ),
+        (
+            r#""\u{D800}""#,
+            "Invalid unicode code point in escape.",
+            1,
+            9,
+        ),
+        (
+            r#""\u{110000}""#,
+            "Invalid unicode code point in escape.",
+            1,
+            11,
+        ),
+    ] {
+        assert_eq!(
+            errors(source),
+            [(
+                message.to_string(),
+                Span::new(start, end, 1, start as u32 + 1)
+            )],
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn invalid_escape_keeps_the_string() {
+    let (tokens, errors) = Scanner::new(r#""a\qb" "\u{}c""#).scan_tokens();
+    assert_eq!(errors.len(), 2);
+    let strings: Vec<_> = tokens
+        .iter()
+        .filter_map(|token| match &token.literal {
+            Some(Literal::Str(s)) => Some(s.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(strings, ["ab", "c"]);
+}
+
+#[test]
+fn backslash_at_end_of_input_is_an_unterminated_string() {
+    assert_eq!(
+        errors("print \"abc\\"),
+        [("Unterminated string.".to_string(), Span::new(6, 11, 1, 7))]
+    );
+}
+
+#[test]
+fn multi_line_string_reports_its_first_line() {
+    let tokens = tokens("\nvar s = \"one\ntwo\nthree\"; x");
+    assert_eq!(tokens[3].token_type, TokenType::String);
+    assert_eq!(tokens[3].span, Span::new(9, 24, 2, 9));
+    // the line count carries on after the string
+    assert_eq!(tokens[5].span.line, 4);
+    assert_eq!(
+        errors("\nprint \"one\ntwo"),
+        [("Unterminated string.".to_string(), Span::new(7, 15, 2, 7))]
+    );
+}
+
+#[test]
+fn nested_block_comments() {
+    let types: Vec<TokenType> = tokens("a /* one /* two */ still one */ b /**/ c /*/**/*/ d")
+        .iter()
+        .map(|token| token.token_type)
+        .collect();
+    assert_eq!(
+        types,
+        [
+            TokenType::Identifer,
+            TokenType::Identifer,
+            TokenType::Identifer,
+            TokenType::Identifer,
+            TokenType::Eof
+        ]
+    );
+}
+
+#[test]
+fn lines_are_counted_inside_block_comments() {
+    let tokens = tokens("/* one\ntwo /* nested\nthree */\nfour */ a\n/*\n*/b");
+    assert_eq!(tokens[0].span, Span::new(38, 39, 4, 9));
+    assert_eq!(tokens[1].span, Span::new(45, 46, 6, 3));
+}
+
+#[test]
+fn unterminated_block_comment() {
+    assert_eq!(
+        errors("a\n  /* one /* two */ three"),
+        [(
+            "Unterminated block comment.".to_string(),
+            Span::new(4, 6, 2, 3)
+        )]
+    );
+    // the nested comment is closed but not the outer one
+    assert_eq!(
+        errors("/* /* */"),
+        [(
+            "Unterminated block comment.".to_string(),
+            Span::new(0, 2, 1, 1)
+        )]
+    );
+}
+
+#[test]
+fn canonically_equivalent_identifiers_are_the_same() {
+    // precomposed `é` and `e` followed by a combining acute accent
+    let tokens = tokens("caf\u{e9} cafe\u{301}");
+    assert_eq!(tokens[0].token_type, TokenType::Identifer);
+    assert_eq!(tokens[1].token_type, TokenType::Identifer);
+    assert_eq!(tokens[0].lexeme, tokens[1].lexeme);
+    assert_eq!(&*tokens[1].lexeme, "caf\u{e9}");
+}
+
+#[test]
+fn columns_count_characters() {
+    let tokens = tokens("var café = \"日本\"; naïve\u{301}x;");
+    let columns: Vec<(u32, usize)> = tokens
+        .iter()
+        .map(|token| (token.span.column, token.span.start))
+        .collect();
+    assert_eq!(
+        columns,
+        [
+            (1, 0),
+            (5, 4),
+            (10, 10),
+            (12, 12),
+            (16, 20),
+            (18, 22),
+            (25, 31),
+            (26, 32)
+        ]
+    );
+}
@@ -0,0 +1,155 @@
+use std::rc::Rc;
+
+use lox_rs::{
+    ast_printer::{AstFormat, AstPrinter},
+    parser::{
+        AssignExpr, BinaryExpr, Expr, Literal, LiteralExpr, Parser, Stmt, VarStmt, VariableExpr,
+    },
+    scanner::Scanner,
+    token::TokenType,
+    visitor::{self, Folder, Visitor, VisitorMut},
+};
+
+// every kind of statement and expression, with `x` somewhere inside each
+const EVERY_NODE: &str = r#"
+var x = 1;
+x = x;
+print -x + (x);
+x and x;
+x += x;
+x++;
+"${x}";
+{ print x; }
+if (x) x; else x;
+while (x) x;
+fun f(a) { return x; }
+class A < B {
+    m() {
+        this.p = x;
+        x.p;
+        super.m();
+        x(x);
+    }
+}
+"#;
+
+fn parse(source: &str) -> Vec<Stmt> {
+    Parser::new(Scanner::new(source)).parse().ok().unwrap()
+}
+
+fn print(statements: &[Stmt]) -> String {
+    AstPrinter::new(AstFormat::SExpr).print(statements)
+}
+
+struct VariableCounter;
+
+impl Visitor<usize> for VariableCounter {
+    fn default_result(&mut self) -> usize {
+        0
+    }
+
+    fn aggregate_result(&mut self, aggregate: usize, next: usize) -> usize {
+        aggregate + next
+    }
+
+    fn visit_variable_expr(&mut self, _expr: &VariableExpr) -> usize {
+        1
+    }
+}
+
+#[test]
+fn visitor_reaches_every_node() {
+    let count: usize = parse(EVERY_NODE)
+        .iter()
+        .map(|stmt| VariableCounter.visit_stmt(stmt))
+        .sum();
+    // every `x` read, plus the superclass `B`
+    assert_eq!(count, 21);
+}
+
+struct Renamer;
+
+impl VisitorMut for Renamer {
+    fn visit_var_stmt(&mut self, stmt: &mut VarStmt) {
+        stmt.name.lexeme = Rc::from(stmt.name.lexeme.replace('x', "y"));
+        visitor::walk_var_stmt_mut(self, stmt);
+    }
+
+    fn visit_assign_expr(&mut self, expr: &mut AssignExpr) {
+        expr.name.lexeme = Rc::from(expr.name.lexeme.replace('x', "y"));
+        visitor::walk_assign_expr_mut(self, expr);
+    }
+
+    fn visit_variable_expr(&mut self, expr: &mut VariableExpr) {
+        expr.name.lexeme = Rc::from(expr.name.lexeme.replace('x', "y"));
+    }
+}
+
+#[test]
+fn visitor_mut_renames_every_identifier() {
+    let mut statements = parse(EVERY_NODE);
+    for stmt in &mut statements {
+        Renamer.visit_stmt(stmt);
+    }
+    assert_eq!(
+        print(&statements),
+        print(&parse(&EVERY_NODE.replace('x', "y")))
+    );
+}
+
+struct ConstantFolder;
+
+impl Folder for ConstantFolder {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        match visitor::fold_expr(self, expr) {
+            Expr::Binary(BinaryExpr {
+                left,
+                operator,
+                right,
+                span,
+            }) if operator.token_type == TokenType::Plus => match (*left, *right) {
+                (
+                    Expr::Literal(LiteralExpr {
+                        value: Literal::Number(a),
+                        ..
+                    }),
+                    Expr::Literal(LiteralExpr {
+                        value: Literal::Number(b),
+                        ..
+                    }),
+                ) => Expr::Literal(LiteralExpr {
+                    value: Literal::Number(a + b),
+                    span,
+                }),
+                (left, right) => Expr::Binary(BinaryExpr {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                    span,
+                }),
+            },
+            expr => expr,
+        }
+    }
+}
+
+#[test]
+fn folder_folds_constants() {
+    let statements: Vec<Stmt> = parse("print 1 + 2; fun f() { return x + (1 + 2) + 3; }")
+        .into_iter()
+        .map(|stmt| ConstantFolder.fold_stmt(stmt))
+        .collect();
+    assert_eq!(
+        print(&statements),
+        "(print 3)\n(fun f (params) (return (+ (+ x (group 3)) 3)))\n"
+    );
+}
+
+#[test]
+fn folder_leaves_every_node_in_place() {
+    let statements: Vec<Stmt> = parse(EVERY_NODE)
+        .into_iter()
+        .map(|stmt| ConstantFolder.fold_stmt(stmt))
+        .collect();
+    assert_eq!(print(&statements), print(&parse(EVERY_NODE)));
+}